@@ -16,7 +16,21 @@
 ///! will begin with `7f00000000000000000000000000000000000000000000000000000000000004e58060`
 ///!
 ///! This script will take path to a file 'gobin_core.wasm' and output 'goblin_core.contract'
-///! in the same folder. To deploy this file call
+///! in the same folder. It exits with an error if the compressed code exceeds the 24KB
+///! contract size limit, so it can be used as a size check.
+///!
+///! To deploy and activate it in one go, pass the `deploy` subcommand. It signs and sends
+///! both transactions with an alloy provider and retries each up to `--retries` times.
+///! Every attempt reuses the same nonce, and a transaction that landed despite a failed
+///! attempt is detected before resending, so a retry never deploys twice.
+///!
+///! ```sh
+///! cargo run --example compile-contract -- deploy \
+///!    --rpc-url http://127.0.0.1:8547 \
+///!    --private-key 0xb6b15c8cb491557369f3c7d2c287b053eb229daa9c22138887752191c9520659
+///! ```
+///!
+///! The equivalent manual steps are
 ///!
 ///! ```sh
 ///! # Run script to generate goblin_core.contract
@@ -35,25 +49,96 @@
 ///!     --value 0.0001ether
 ///! ```
 ///!
-use alloy_primitives::U256;
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{address, Address, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::SolCall,
+};
 use brotli2::read::BrotliEncoder;
-use eyre::{Result, WrapErr};
+use eyre::{bail, eyre, Result, WrapErr};
 use std::fs;
+use std::future::Future;
 use std::io::Read;
 use std::ops::Range;
 use std::path::PathBuf;
+use std::time::Duration;
 use wasm_encoder::{Module, RawSection};
 use wasmparser::{Parser, Payload};
 
 const PROJECT_HASH_SECTION_NAME: &str = "project_hash";
 const BROTLI_COMPRESSION_LEVEL: u32 = 11;
 const EOF_PREFIX_NO_DICT: &str = "EFF00000";
+const ARB_WASM_ADDRESS: Address = address!("0000000000000000000000000000000000000071");
+// 0.0001 ether
+const ACTIVATION_FEE_WEI: u64 = 100_000_000_000_000;
+const DEFAULT_RETRIES: u32 = 3;
+
+// Stylus rejects compressed contract code above the EVM code size limit of 24KB
+const MAX_CONTRACT_CODE_SIZE: usize = 24 * 1024;
+
+sol! {
+    interface ArbWasm {
+        function activateProgram(address program)
+            external
+            payable
+            returns (uint16 version, uint256 dataFee);
+
+        function programVersion(address program) external view returns (uint16 version);
+    }
+}
+
+/// Flags for the `deploy` subcommand
+struct DeployArgs {
+    rpc_url: String,
+    private_key: String,
+    retries: u32,
+}
+
+impl DeployArgs {
+    /// Parses `deploy --rpc-url <url> --private-key <key> [--retries <n>]`.
+    /// Returns None if the subcommand is absent.
+    fn parse(args: &[String]) -> Result<Option<Self>> {
+        if args.first().map(String::as_str) != Some("deploy") {
+            return Ok(None);
+        }
+
+        let mut rpc_url = None;
+        let mut private_key = None;
+        let mut retries = DEFAULT_RETRIES;
+
+        let mut iter = args[1..].iter();
+        while let Some(flag) = iter.next() {
+            let value = iter
+                .next()
+                .ok_or_else(|| eyre!("missing value for {}", flag))?;
+            match flag.as_str() {
+                "--rpc-url" => rpc_url = Some(value.clone()),
+                "--private-key" => private_key = Some(value.clone()),
+                "--retries" => retries = value.parse().wrap_err("invalid --retries")?,
+                _ => bail!("unknown flag {}", flag),
+            }
+        }
+
+        Ok(Some(DeployArgs {
+            rpc_url: rpc_url.ok_or_else(|| eyre!("--rpc-url is required"))?,
+            private_key: private_key.ok_or_else(|| eyre!("--private-key is required"))?,
+            retries,
+        }))
+    }
+}
 
 // To run
 //
 // cargo run -p compile-contract --bin compile-contract
 // Run button gives incorrect path
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let deploy_args = DeployArgs::parse(&args)?;
+
     // Hardcoded path to WASM file - replace with your actual path
     let wasm_path = PathBuf::from("./target/wasm32-unknown-unknown/release/goblin_core_v1.wasm");
 
@@ -79,11 +164,144 @@ fn main() -> Result<()> {
         fs::metadata(&wasm_path)?.len()
     );
     println!("Processed WASM size: {} bytes", wasm.len());
-    println!("Contract code size: {} bytes", init_code.len());
+    println!(
+        "Contract code size: {} bytes ({:.1}% of {} byte limit)",
+        init_code.len(),
+        init_code.len() as f64 * 100.0 / MAX_CONTRACT_CODE_SIZE as f64,
+        MAX_CONTRACT_CODE_SIZE
+    );
+
+    if init_code.len() > MAX_CONTRACT_CODE_SIZE {
+        bail!(
+            "contract code exceeds the size limit by {} bytes",
+            init_code.len() - MAX_CONTRACT_CODE_SIZE
+        );
+    }
+
+    if let Some(deploy_args) = deploy_args {
+        let contract_address = deploy_and_activate(&deploy_args, &deployment_data)?;
+        println!("Deployed and activated at: {}", contract_address);
+    }
 
     Ok(())
 }
 
+/// Sends the creation transaction, then activates the program through ArbWasm.
+/// Returns the deployed contract address.
+fn deploy_and_activate(args: &DeployArgs, deployment_data: &[u8]) -> Result<Address> {
+    let signer: PrivateKeySigner = args.private_key.parse().wrap_err("invalid --private-key")?;
+    let deployer = signer.address();
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(args.rpc_url.parse().wrap_err("invalid --rpc-url")?);
+
+    tokio::runtime::Runtime::new()?.block_on(async {
+        // The address only depends on the nonce, so it is known before the transaction lands
+        let nonce = provider.get_transaction_count(deployer).pending().await?;
+        let contract_address = deployer.create(nonce);
+
+        let create_tx = TransactionRequest::default()
+            .with_deploy_code(deployment_data.to_vec())
+            .with_nonce(nonce);
+        send_with_retries(args, &provider, deployer, create_tx, "deploy", || async {
+            Ok(!provider.get_code_at(contract_address).await?.is_empty())
+        })
+        .await?;
+        println!("Deployed contract at: {}", contract_address);
+
+        let activate_tx = TransactionRequest::default()
+            .with_to(ARB_WASM_ADDRESS)
+            .with_input(
+                ArbWasm::activateProgramCall {
+                    program: contract_address,
+                }
+                .abi_encode(),
+            )
+            .with_value(U256::from(ACTIVATION_FEE_WEI))
+            .with_nonce(nonce + 1);
+        send_with_retries(args, &provider, deployer, activate_tx, "activate", || {
+            is_activated(&provider, contract_address)
+        })
+        .await?;
+
+        Ok(contract_address)
+    })
+}
+
+/// Signs and sends `tx` from `sender`, retrying with a linear backoff.
+///
+/// The nonce of `tx` is set by the caller, so every attempt replaces the same transaction.
+/// A failed attempt may still have landed, e.g. if the RPC timed out after accepting
+/// the transaction. Once the nonce is used, `landed` tells whether that transaction
+/// succeeded and the loop stops instead of sending a second one.
+async fn send_with_retries<P, F, Fut>(
+    args: &DeployArgs,
+    provider: &P,
+    sender: Address,
+    tx: TransactionRequest,
+    label: &str,
+    landed: F,
+) -> Result<()>
+where
+    P: Provider,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let nonce = tx
+        .nonce
+        .ok_or_else(|| eyre!("{} transaction has no nonce", label))?;
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = match provider.send_transaction(tx.clone()).await {
+            Ok(pending) => match pending.get_receipt().await {
+                Ok(receipt) if receipt.status() => return Ok(()),
+                Ok(receipt) => bail!(
+                    "{} transaction {} reverted",
+                    label,
+                    receipt.transaction_hash
+                ),
+                Err(error) => error.to_string(),
+            },
+            Err(error) => error.to_string(),
+        };
+
+        if provider.get_transaction_count(sender).pending().await? > nonce {
+            if landed().await? {
+                println!("{} attempt {} landed despite the error", label, attempt);
+                return Ok(());
+            }
+            bail!(
+                "{} transaction with nonce {} was mined but failed: {}",
+                label,
+                nonce,
+                error
+            );
+        }
+
+        if attempt > args.retries {
+            bail!("{} failed after {} attempts: {}", label, attempt, error);
+        }
+        println!("{} attempt {} failed, retrying: {}", label, attempt, error);
+        tokio::time::sleep(Duration::from_secs(2 * attempt as u64)).await;
+    }
+}
+
+/// Whether the program at `address` is activated. ArbWasm reverts for programs that
+/// are not.
+async fn is_activated<P: Provider>(provider: &P, address: Address) -> Result<bool> {
+    let tx = TransactionRequest::default()
+        .with_to(ARB_WASM_ADDRESS)
+        .with_input(ArbWasm::programVersionCall { program: address }.abi_encode());
+
+    let Ok(output) = provider.call(&tx).await else {
+        return Ok(false);
+    };
+    let version = ArbWasm::programVersionCall::abi_decode_returns(&output, true)?.version;
+    Ok(version != 0)
+}
+
 /// Reads a WASM file at a specified path and returns its brotli compressed bytes.
 fn compress_wasm(wasm: &PathBuf, project_hash: [u8; 32]) -> Result<(Vec<u8>, Vec<u8>)> {
     let wasm = fs::read(wasm)?;
@@ -0,0 +1,358 @@
+//! Generate the wire format test vectors in `test-vectors/goblin-core.json`
+//!
+//! Payloads are laid out from `test-vectors/goblin-core-interface.json`, the selector
+//! table exported by the crate, the way an SDK in another language would encode them.
+//! `cargo test test_vectors` then runs every vector through the contract's entrypoint.
+//!
+//! ```sh
+//! cargo run --example generate-test-vectors
+//! ```
+//!
+//! Condensed orders and reduce packets have no selector in this contract, so there are
+//! no vectors for them.
+//!
+use eyre::{bail, eyre, Result};
+use hex_literal::hex;
+use serde_json::Value;
+use std::fs;
+
+const INTERFACE_PATH: &str = "./test-vectors/goblin-core-interface.json";
+const VECTORS_PATH: &str = "./test-vectors/goblin-core.json";
+
+const TOKEN: [u8; 20] = hex!("7e32b54800705876d3b5cfbc7d9c226a211f7c1a");
+const TRADER: [u8; 20] = hex!("3f1eae7d46d88f08fc2f8ed27fcb2ab183eb2d0e");
+const RECIPIENT: [u8; 20] = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+const ZERO_ADDRESS: [u8; 20] = [0u8; 20];
+
+/// Withdraw the whole free balance
+const WITHDRAW_ALL: u64 = u64::MAX;
+
+/// Withdraw the free balance minus the lots in the lower 63 bits
+const ALL_MINUS_FLAG: u64 = 1 << 63;
+
+/// Registry list of operators
+const OPERATOR: u8 = 0;
+
+/// A named payload input. Padding is taken from the interface.
+enum Input {
+    Address(&'static str, [u8; 20]),
+    Lots(&'static str, u64),
+    Uint256(&'static str, u64),
+    Uint8(&'static str, u8),
+}
+
+impl Input {
+    fn name(&self) -> &'static str {
+        match self {
+            Input::Address(name, _)
+            | Input::Lots(name, _)
+            | Input::Uint256(name, _)
+            | Input::Uint8(name, _) => name,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Input::Address(..) => "Address",
+            Input::Lots(..) => "Lots",
+            Input::Uint256(..) => "Uint256",
+            Input::Uint8(..) => "Uint8",
+        }
+    }
+
+    fn encode(&self, calldata: &mut Vec<u8>) {
+        match self {
+            Input::Address(_, address) => calldata.extend_from_slice(address),
+            Input::Lots(_, lots) => calldata.extend_from_slice(&lots.to_le_bytes()),
+            Input::Uint256(_, value) => {
+                calldata.extend_from_slice(&[0u8; 24]);
+                calldata.extend_from_slice(&value.to_be_bytes());
+            }
+            Input::Uint8(_, byte) => calldata.push(*byte),
+        }
+    }
+
+    /// Large integers are quoted so that JSON parsers don't round them
+    fn to_json(&self) -> String {
+        match self {
+            Input::Address(name, address) => {
+                format!("\"{}\": \"0x{}\"", name, hex::encode(address))
+            }
+            Input::Lots(name, value) | Input::Uint256(name, value) => {
+                format!("\"{}\": \"{}\"", name, value)
+            }
+            Input::Uint8(name, byte) => format!("\"{}\": {}", name, byte),
+        }
+    }
+}
+
+struct Call {
+    name: &'static str,
+    inputs: Vec<Input>,
+}
+
+struct Vector {
+    name: &'static str,
+    calls: Vec<Call>,
+}
+
+fn call(name: &'static str, inputs: Vec<Input>) -> Call {
+    Call { name, inputs }
+}
+
+fn single(name: &'static str, call: Call) -> Vector {
+    Vector {
+        name,
+        calls: vec![call],
+    }
+}
+
+fn vectors() -> Vec<Vector> {
+    use Input::*;
+
+    vec![
+        single(
+            "credit_eth",
+            call("credit_eth", vec![Address("recipient", TRADER)]),
+        ),
+        single(
+            "credit_erc20",
+            call(
+                "credit_erc20",
+                vec![
+                    Address("token", TOKEN),
+                    Address("recipient", TRADER),
+                    Lots("lots", 1),
+                ],
+            ),
+        ),
+        single(
+            "withdraw_eth",
+            call(
+                "withdraw_eth",
+                vec![Address("recipient", RECIPIENT), Lots("lots", 2)],
+            ),
+        ),
+        single(
+            "withdraw_erc20_all",
+            call(
+                "withdraw_erc20",
+                vec![
+                    Address("token", TOKEN),
+                    Address("recipient", RECIPIENT),
+                    Lots("lots", WITHDRAW_ALL),
+                ],
+            ),
+        ),
+        single(
+            "withdraw_erc20_all_minus",
+            call(
+                "withdraw_erc20",
+                vec![
+                    Address("token", TOKEN),
+                    Address("recipient", ZERO_ADDRESS),
+                    Lots("lots", ALL_MINUS_FLAG | 3),
+                ],
+            ),
+        ),
+        single(
+            "lock_funds",
+            call(
+                "lock_funds",
+                vec![
+                    Address("trader", TRADER),
+                    Address("token", TOKEN),
+                    Lots("lots", 5),
+                ],
+            ),
+        ),
+        single(
+            "unlock_funds",
+            call(
+                "unlock_funds",
+                vec![
+                    Address("trader", TRADER),
+                    Address("token", TOKEN),
+                    Lots("lots", 5),
+                ],
+            ),
+        ),
+        single(
+            "settle_funds",
+            call(
+                "settle_funds",
+                vec![
+                    Address("token", TOKEN),
+                    Address("from", TRADER),
+                    Address("to", RECIPIENT),
+                    Lots("lots", 5),
+                ],
+            ),
+        ),
+        single(
+            "set_trader_config",
+            call(
+                "set_trader_config",
+                vec![
+                    Address("default_recipient", RECIPIENT),
+                    Uint8("settlement_hook", 1),
+                    Uint8("operator_allowlist", 1),
+                ],
+            ),
+        ),
+        single(
+            "credit_erc20_atoms",
+            call(
+                "credit_erc20_atoms",
+                vec![
+                    Address("token", TOKEN),
+                    Address("recipient", TRADER),
+                    Uint256("atoms", 2_500_000),
+                ],
+            ),
+        ),
+        single(
+            "withdraw_erc20_atoms",
+            call(
+                "withdraw_erc20_atoms",
+                vec![
+                    Address("token", TOKEN),
+                    Address("recipient", RECIPIENT),
+                    Uint256("atoms", 1_999_999),
+                ],
+            ),
+        ),
+        single(
+            "get_trader_token_state",
+            call(
+                "get_trader_token_state",
+                vec![Address("trader", TRADER), Address("token", TOKEN)],
+            ),
+        ),
+        single(
+            "get_interface_version",
+            call("get_interface_version", vec![]),
+        ),
+        single(
+            "get_token_preflight",
+            call(
+                "get_token_preflight",
+                vec![Address("trader", TRADER), Address("token", TOKEN)],
+            ),
+        ),
+        single(
+            "set_registry_entry",
+            call(
+                "set_registry_entry",
+                vec![
+                    Address("account", RECIPIENT),
+                    Uint8("list", OPERATOR),
+                    Uint8("registered", 1),
+                ],
+            ),
+        ),
+        Vector {
+            name: "batch_credit_and_withdraw",
+            calls: vec![
+                call("credit_eth", vec![Address("recipient", TRADER)]),
+                call(
+                    "withdraw_eth",
+                    vec![Address("recipient", RECIPIENT), Lots("lots", 1)],
+                ),
+            ],
+        },
+    ]
+}
+
+/// Append `call` as selector, payload length and payload laid out by `interface`
+fn encode_call(interface: &Value, call: &Call, calldata: &mut Vec<u8>) -> Result<u8> {
+    let entry = interface["selectors"]
+        .as_array()
+        .ok_or_else(|| eyre!("interface has no selectors"))?
+        .iter()
+        .find(|entry| entry["name"] == call.name)
+        .ok_or_else(|| eyre!("unknown call {}", call.name))?;
+    let selector = entry["selector"].as_u64().unwrap() as u8;
+    let payload_len = entry["payload_len"].as_u64().unwrap() as usize;
+
+    calldata.push(selector);
+    calldata.extend_from_slice(&(payload_len as u16).to_le_bytes());
+    let start = calldata.len();
+
+    let mut inputs = call.inputs.iter();
+    for field in entry["payload"].as_array().unwrap() {
+        if field["kind"] == "Padding" {
+            let len = field["len"].as_u64().unwrap() as usize;
+            calldata.extend(std::iter::repeat(0).take(len));
+            continue;
+        }
+
+        let Some(input) = inputs.next() else {
+            bail!("{}: missing input {}", call.name, field["name"]);
+        };
+        if field["name"] != input.name() || field["kind"] != input.kind() {
+            bail!(
+                "{}: expected {} {}, got {} {}",
+                call.name,
+                field["kind"],
+                field["name"],
+                input.kind(),
+                input.name()
+            );
+        }
+        input.encode(calldata);
+    }
+    if let Some(input) = inputs.next() {
+        bail!("{}: unexpected input {}", call.name, input.name());
+    }
+
+    if calldata.len() - start != payload_len {
+        bail!("{}: fields don't add up to the payload length", call.name);
+    }
+
+    Ok(selector)
+}
+
+fn to_json(interface: &Value, vectors: &[Vector]) -> Result<String> {
+    let mut json = String::from("[\n");
+
+    for (i, vector) in vectors.iter().enumerate() {
+        let mut calldata = vec![vector.calls.len() as u8];
+        let mut calls = Vec::new();
+        for call in &vector.calls {
+            let selector = encode_call(interface, call, &mut calldata)?;
+            let inputs: Vec<String> = call.inputs.iter().map(Input::to_json).collect();
+            calls.push(format!(
+                "      {{ \"selector\": {}, \"inputs\": {{ {} }} }}",
+                selector,
+                inputs.join(", ")
+            ));
+        }
+
+        json += "  {\n";
+        json += &format!("    \"name\": \"{}\",\n", vector.name);
+        json += &format!("    \"header\": {},\n", vector.calls.len());
+        json += "    \"calls\": [\n";
+        json += &calls.join(",\n");
+        json += "\n    ],\n";
+        json += &format!("    \"calldata\": \"0x{}\"\n", hex::encode(&calldata));
+        json += if i + 1 < vectors.len() {
+            "  },\n"
+        } else {
+            "  }\n"
+        };
+    }
+
+    json += "]\n";
+    Ok(json)
+}
+
+fn main() -> Result<()> {
+    let interface: Value = serde_json::from_str(&fs::read_to_string(INTERFACE_PATH)?)?;
+
+    let json = to_json(&interface, &vectors())?;
+    fs::write(VECTORS_PATH, json)?;
+    println!("Wrote {}", VECTORS_PATH);
+
+    Ok(())
+}
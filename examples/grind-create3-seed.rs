@@ -1,13 +1,37 @@
 use alloy_primitives::{address, keccak256, Address, B256, U256};
+use alloy_sol_types::{sol, SolCall};
 use hex_literal::hex;
 use rayon::prelude::*;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::fs;
+use std::time::Instant;
 
 const DEPLOYER: Address = address!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
 const FACTORY_ADDRESS: Address = address!("A6E41fFD769491a42A6e5Ce453259b93983a22EF");
 const PROXY_BYTECODE: [u8; 16] = hex!("67363d3d37363d34f03d5260086018f3");
-const DESIRED_PREFIX: [u8; 2] = hex!("8888"); // Define desired prefix as bytes
+const DEFAULT_PATTERN: &str = "prefix:8888";
+
+// Salts scanned by each worker between checkpoints
+const CHUNK_SIZE: u64 = 1 << 20;
+
+// Search parameters and the next unscanned salt, so long searches can resume
+const CHECKPOINT_PATH: &str = "./target/grind-create3-seed.checkpoint";
+
+// Creation code written by compile-contract
+const CONTRACT_PATH: &str = "./target/wasm32-unknown-unknown/release/goblin_core_v1.contract";
+
+sol! {
+    function deploy(bytes32 salt, bytes memory creationCode) external payable returns (address deployed);
+}
+
+/// Calldata for `CREATE3Factory.deploy(salt, creation_code)`. The factory namespaces
+/// the salt with msg.sender, so it must be sent from `DEPLOYER`.
+fn deploy_calldata(salt: B256, creation_code: Vec<u8>) -> Vec<u8> {
+    deployCall {
+        salt,
+        creationCode: creation_code,
+    }
+    .abi_encode()
+}
 
 /// Namespace the salt by hashing the deployer address with the provided salt.
 fn namespace_salt(deployer: Address, salt: B256) -> B256 {
@@ -40,45 +64,251 @@ fn get_create3_address(
     )
 }
 
-/// Search for a salt that produces an address with the desired prefix.
+/// An address pattern over hex nibbles.
+///
+/// - `prefix:<hex>` and `suffix:<hex>` match the start or end of the address
+/// - `mask:<40 chars>` matches every nibble, with `.` as a wildcard
+#[derive(Debug, PartialEq)]
+enum Pattern {
+    Prefix(Vec<u8>),
+    Suffix(Vec<u8>),
+    Mask(Vec<Option<u8>>),
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Option<Pattern> {
+        let (kind, value) = pattern.split_once(':')?;
+        let value = value.strip_prefix("0x").unwrap_or(value);
+
+        match kind {
+            "prefix" => Some(Pattern::Prefix(parse_nibbles(value)?)),
+            "suffix" => Some(Pattern::Suffix(parse_nibbles(value)?)),
+            "mask" if value.len() == 40 => {
+                let mask = value
+                    .chars()
+                    .map(|c| match c {
+                        '.' => Some(None),
+                        c => c.to_digit(16).map(|nibble| Some(nibble as u8)),
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Pattern::Mask(mask))
+            }
+            _ => None,
+        }
+    }
+
+    fn matches(&self, address: &Address) -> bool {
+        let nibble = |i: usize| {
+            let byte = address[i / 2];
+            if i % 2 == 0 {
+                byte >> 4
+            } else {
+                byte & 0x0f
+            }
+        };
+
+        match self {
+            Pattern::Prefix(prefix) => prefix.iter().enumerate().all(|(i, n)| nibble(i) == *n),
+            Pattern::Suffix(suffix) => {
+                let start = 40 - suffix.len();
+                suffix
+                    .iter()
+                    .enumerate()
+                    .all(|(i, n)| nibble(start + i) == *n)
+            }
+            Pattern::Mask(mask) => mask
+                .iter()
+                .enumerate()
+                .all(|(i, n)| n.map_or(true, |n| nibble(i) == n)),
+        }
+    }
+}
+
+/// Parses up to 40 hex characters into nibbles.
+fn parse_nibbles(value: &str) -> Option<Vec<u8>> {
+    if value.is_empty() || value.len() > 40 {
+        return None;
+    }
+    value
+        .chars()
+        .map(|c| c.to_digit(16).map(|nibble| nibble as u8))
+        .collect()
+}
+
+/// Scan `workers` contiguous chunks of salts beginning at `start`. Every worker owns
+/// its chunk and scans it to the end, so no iterations are spent polling a shared
+/// flag. Returns all matches in ascending salt order.
+fn scan_salts(
+    factory: Address,
+    deployer: Address,
+    proxy_bytecode_hash: B256,
+    patterns: &[Pattern],
+    start: u64,
+    workers: u64,
+    chunk_size: u64,
+) -> Vec<(B256, Address)> {
+    (0..workers)
+        .into_par_iter()
+        .flat_map_iter(|worker| {
+            let chunk_start = start + worker * chunk_size;
+            (chunk_start..chunk_start + chunk_size).filter_map(move |i| {
+                let salt = B256::from(U256::from(i));
+                let address = get_create3_address(factory, deployer, salt, proxy_bytecode_hash);
+
+                patterns
+                    .iter()
+                    .any(|pattern| pattern.matches(&address))
+                    .then_some((salt, address))
+            })
+        })
+        .collect()
+}
+
+/// Scan progress of a search. The salt is only valid for the same factory, deployer
+/// and patterns, so a checkpoint for other parameters is discarded.
+#[derive(Debug, PartialEq)]
+struct Checkpoint {
+    params: String,
+    next: u64,
+}
+
+impl Checkpoint {
+    /// Identifies a search by its factory, deployer and patterns
+    fn params(factory: Address, deployer: Address, patterns: &[Pattern]) -> String {
+        format!("{:?} {:?} {:?}", factory, deployer, patterns)
+    }
+
+    /// Parses the parameters line followed by the next salt
+    fn parse(contents: &str) -> Option<Checkpoint> {
+        let (params, next) = contents.trim_end().rsplit_once('\n')?;
+        Some(Checkpoint {
+            params: params.to_string(),
+            next: next.parse().ok()?,
+        })
+    }
+
+    fn encode(&self) -> String {
+        format!("{}\n{}\n", self.params, self.next)
+    }
+
+    /// Next salt to scan for `params`, or 0 if the saved checkpoint is missing or
+    /// belongs to another search
+    fn load(params: &str) -> u64 {
+        match fs::read_to_string(CHECKPOINT_PATH)
+            .ok()
+            .and_then(|contents| Checkpoint::parse(&contents))
+        {
+            Some(checkpoint) if checkpoint.params == params => checkpoint.next,
+            Some(_) => {
+                println!("Checkpoint is for different parameters, starting over");
+                0
+            }
+            None => 0,
+        }
+    }
+
+    fn save(&self) {
+        fs::write(CHECKPOINT_PATH, self.encode()).unwrap();
+    }
+}
+
+/// Search for a salt that produces an address matching any pattern, resuming from
+/// the checkpoint file and reporting throughput after each round.
+///
+/// A found salt is checkpointed as the next salt, so running the same search again
+/// finds it in the first round.
 fn find_salt(
     factory: Address,
     deployer: Address,
     proxy_bytecode_hash: B256,
-    desired_prefix: &[u8],
+    patterns: &[Pattern],
 ) -> Option<B256> {
-    let found = Arc::new(AtomicBool::new(false));
+    let workers = rayon::current_num_threads() as u64;
+    let round_size = workers * CHUNK_SIZE;
 
-    (0u64..u64::MAX).into_par_iter().find_map_any(|i| {
-        if found.load(Ordering::Relaxed) {
-            return None;
-        }
+    let params = Checkpoint::params(factory, deployer, patterns);
+    let mut next = Checkpoint::load(&params);
+    if next != 0 {
+        println!("Resuming from salt {}", next);
+    }
 
-        let salt = B256::from(U256::try_from(i).unwrap());
-        let address = get_create3_address(factory, deployer, salt, proxy_bytecode_hash);
+    while next <= u64::MAX - round_size {
+        let started_at = Instant::now();
+        let matches = scan_salts(
+            factory,
+            deployer,
+            proxy_bytecode_hash,
+            patterns,
+            next,
+            workers,
+            CHUNK_SIZE,
+        );
+        println!(
+            "Scanned up to salt {} at {:.0} salts/s",
+            next + round_size,
+            round_size as f64 / started_at.elapsed().as_secs_f64()
+        );
 
-        if address.as_slice().starts_with(desired_prefix) {
+        for (salt, address) in &matches {
             println!("Found address {:?} for salt {:?}", address, salt);
-            found.store(true, Ordering::Relaxed);
-            Some(salt)
-        } else {
-            None
         }
-    })
+        if let Some((salt, _)) = matches.first() {
+            Checkpoint {
+                params,
+                next: U256::from_be_bytes(salt.0).to::<u64>(),
+            }
+            .save();
+            return Some(*salt);
+        }
+
+        next += round_size;
+        Checkpoint {
+            params: params.clone(),
+            next,
+        }
+        .save();
+    }
+
+    None
 }
 
 fn main() {
     let proxy_bytecode_hash = keccak256(PROXY_BYTECODE);
 
-    println!("Starting search for CREATE3 salt...");
+    // Patterns are passed as arguments, e.g. `prefix:8888 suffix:0000`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let patterns = if args.is_empty() {
+        vec![DEFAULT_PATTERN.to_string()]
+    } else {
+        args
+    }
+    .iter()
+    .map(|arg| Pattern::parse(arg).unwrap_or_else(|| panic!("Invalid pattern {}", arg)))
+    .collect::<Vec<_>>();
+
+    println!(
+        "Starting search for CREATE3 salt matching {:?}...",
+        patterns
+    );
 
-    match find_salt(
-        FACTORY_ADDRESS,
-        DEPLOYER,
-        proxy_bytecode_hash.into(),
-        &DESIRED_PREFIX,
-    ) {
-        Some(salt) => println!("Found matching salt: {:?}", salt),
+    match find_salt(FACTORY_ADDRESS, DEPLOYER, proxy_bytecode_hash, &patterns) {
+        Some(salt) => {
+            println!("Found matching salt: {:?}", salt);
+
+            match fs::read(CONTRACT_PATH) {
+                Ok(creation_code) => {
+                    let calldata = deploy_calldata(salt, creation_code);
+                    let calldata_path = format!("{}.calldata", CONTRACT_PATH);
+                    fs::write(&calldata_path, hex::encode(&calldata)).unwrap();
+                    println!(
+                        "Factory deploy calldata ({} bytes) written to: {}",
+                        calldata.len(),
+                        calldata_path
+                    );
+                }
+                Err(_) => println!("{} not found, run compile-contract first", CONTRACT_PATH),
+            }
+        }
         None => println!("No matching salt found."),
     }
 }
@@ -101,4 +331,100 @@ mod test {
         //     address!("8888415db80eabcf580283a3d65249887d3161b0")
         // );
     }
+
+    #[test]
+    fn test_pattern_matches() {
+        let address = address!("8888415db80eabcf580283a3d65249887d3161b0");
+
+        assert!(Pattern::parse("prefix:888").unwrap().matches(&address));
+        assert!(Pattern::parse("prefix:0x88884").unwrap().matches(&address));
+        assert!(!Pattern::parse("prefix:8889").unwrap().matches(&address));
+        assert!(Pattern::parse("suffix:161b0").unwrap().matches(&address));
+        assert!(!Pattern::parse("suffix:61b1").unwrap().matches(&address));
+        assert!(Pattern::parse("mask:8888..........................................b0").is_none());
+        assert!(
+            Pattern::parse("mask:8888..................................b0")
+                .unwrap()
+                .matches(&address)
+        );
+        assert!(
+            !Pattern::parse("mask:8888..................................b1")
+                .unwrap()
+                .matches(&address)
+        );
+        assert!(Pattern::parse("prefix:zz").is_none());
+        assert!(Pattern::parse("8888").is_none());
+    }
+
+    #[test]
+    fn test_scan_salts_partitions_range() {
+        let proxy_bytecode_hash = keccak256(PROXY_BYTECODE);
+        let salt = B256::from(U256::from(5));
+        let address = get_create3_address(FACTORY_ADDRESS, DEPLOYER, salt, proxy_bytecode_hash);
+        let patterns = [Pattern::Mask(
+            address
+                .iter()
+                .flat_map(|byte| [Some(byte >> 4), Some(byte & 0x0f)])
+                .collect(),
+        )];
+
+        // Salt 5 is in the second worker's chunk
+        let matches = scan_salts(
+            FACTORY_ADDRESS,
+            DEPLOYER,
+            proxy_bytecode_hash,
+            &patterns,
+            0,
+            2,
+            4,
+        );
+        assert_eq!(matches, vec![(salt, address)]);
+
+        let matches = scan_salts(
+            FACTORY_ADDRESS,
+            DEPLOYER,
+            proxy_bytecode_hash,
+            &patterns,
+            6,
+            2,
+            4,
+        );
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let patterns = [Pattern::parse("prefix:8888").unwrap()];
+        let params = Checkpoint::params(FACTORY_ADDRESS, DEPLOYER, &patterns);
+        let checkpoint = Checkpoint {
+            params: params.clone(),
+            next: 42,
+        };
+        assert_eq!(Checkpoint::parse(&checkpoint.encode()), Some(checkpoint));
+
+        // Any changed parameter gives a different key
+        let other_patterns = [Pattern::parse("prefix:8889").unwrap()];
+        assert_ne!(
+            Checkpoint::params(FACTORY_ADDRESS, DEPLOYER, &other_patterns),
+            params
+        );
+        assert_ne!(Checkpoint::params(DEPLOYER, DEPLOYER, &patterns), params);
+
+        // Checkpoints of the old format hold only the salt and are discarded
+        assert_eq!(Checkpoint::parse("42"), None);
+    }
+
+    #[test]
+    fn test_deploy_calldata() {
+        let salt = B256::with_last_byte(1);
+        let calldata = deploy_calldata(salt, vec![0xef, 0xf0, 0x00, 0x00]);
+
+        assert_eq!(calldata[0..4], keccak256("deploy(bytes32,bytes)")[0..4]);
+        assert_eq!(calldata[4..36], salt[..]);
+        // Offset of creationCode, then its length and right padded bytes
+        assert_eq!(calldata[36..68], B256::with_last_byte(0x40)[..]);
+        assert_eq!(calldata[68..100], B256::with_last_byte(4)[..]);
+        assert_eq!(calldata[100..104], [0xef, 0xf0, 0x00, 0x00]);
+        assert_eq!(calldata.len(), 132);
+    }
 }
@@ -2,6 +2,7 @@
 
 readonly NUM_CALLS=01
 readonly DEPOSIT_ERC20_SELECTOR=01
+readonly DEPOSIT_ERC20_PAYLOAD_LEN=3000 # 48 bytes in little endian
 readonly TOKEN=7E32b54800705876d3b5cFbc7d9c226a211F7C1a
 readonly LOTS=0100000000000000
 
@@ -10,12 +11,13 @@ cast send $BASE_TOKEN \
     --private-key $PRIVATE_KEY
 
 cast send $CONTRACT \
-    "0x$NUM_CALLS$DEPOSIT_ERC20_SELECTOR${BASE_TOKEN#0x}${ADDRESS#0x}$LOTS" \
+    "0x$NUM_CALLS$DEPOSIT_ERC20_SELECTOR$DEPOSIT_ERC20_PAYLOAD_LEN${BASE_TOKEN#0x}${ADDRESS#0x}$LOTS" \
     --private-key $PRIVATE_KEY
 
 readonly GET_TRADER_STATE_SELECTOR=0A
+readonly GET_TRADER_STATE_PAYLOAD_LEN=2800 # 40 bytes in little endian
 
 # Check trader state
 echo "Trader state-"
 cast call $CONTRACT \
-    "0x$NUM_CALLS$GET_TRADER_STATE_SELECTOR${ADDRESS#0x}${BASE_TOKEN#0x}"
+    "0x$NUM_CALLS$GET_TRADER_STATE_SELECTOR$GET_TRADER_STATE_PAYLOAD_LEN${ADDRESS#0x}${BASE_TOKEN#0x}"
@@ -9,7 +9,8 @@ cast call $BASE_TOKEN "allowance(address,address)" $ADDRESS $CONTRACT
 # Check trader state
 readonly NUM_CALLS=01
 readonly GET_TRADER_STATE_SELECTOR=0A
+readonly GET_TRADER_STATE_PAYLOAD_LEN=2800 # 40 bytes in little endian
 
 echo "Trader state"
 cast call $CONTRACT \
-    "0x$NUM_CALLS$GET_TRADER_STATE_SELECTOR${ADDRESS#0x}${BASE_TOKEN#0x}"
+    "0x$NUM_CALLS$GET_TRADER_STATE_SELECTOR$GET_TRADER_STATE_PAYLOAD_LEN${ADDRESS#0x}${BASE_TOKEN#0x}"
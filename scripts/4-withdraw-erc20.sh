@@ -0,0 +1,14 @@
+#!/bin/bash
+
+readonly NUM_CALLS=01
+readonly WITHDRAW_ERC20_SELECTOR=03
+readonly WITHDRAW_ERC20_PAYLOAD_LEN=3000 # 48 bytes in little endian
+readonly LOTS=0100000000000000
+
+# Withdraw lots of BASE_TOKEN to ADDRESS
+cast send $CONTRACT \
+    "0x$NUM_CALLS$WITHDRAW_ERC20_SELECTOR$WITHDRAW_ERC20_PAYLOAD_LEN${BASE_TOKEN#0x}${ADDRESS#0x}$LOTS" \
+    --private-key $PRIVATE_KEY
+
+echo "balance"
+cast call $BASE_TOKEN "balanceOf(address)(uint256)" $ADDRESS
@@ -0,0 +1,57 @@
+#!/bin/bash
+# End to end check of goblin-core on the local node. Run after 1-deploy-localnet.sh.
+# Deposits one lot of BASE_TOKEN, withdraws it and asserts balances after each step.
+
+set -euo pipefail
+
+readonly NUM_CALLS=01
+readonly DEPOSIT_ERC20_SELECTOR=01
+readonly WITHDRAW_ERC20_SELECTOR=03
+readonly ERC20_PAYLOAD_LEN=3000 # 48 bytes in little endian
+readonly GET_TRADER_STATE_SELECTOR=0A
+readonly GET_TRADER_STATE_PAYLOAD_LEN=2800 # 40 bytes in little endian
+readonly LOTS=0100000000000000
+readonly ATOMS_PER_LOT=1000000
+
+fail() {
+    echo "FAIL: $1"
+    exit 1
+}
+
+# Free lots of ADDRESS in BASE_TOKEN
+# The trader state is lots_locked | lots_free | lots_locked_high | lots_free_high, each
+# 8 bytes little endian. Only the low word is read, the test moves a few lots.
+lots_free() {
+    local state
+    state=$(cast call $CONTRACT \
+        "0x$NUM_CALLS$GET_TRADER_STATE_SELECTOR$GET_TRADER_STATE_PAYLOAD_LEN${ADDRESS#0x}${BASE_TOKEN#0x}")
+    cast to-dec 0x$(echo ${state:18:16} | fold -w2 | tac | tr -d '\n')
+}
+
+token_balance() {
+    cast call $BASE_TOKEN "balanceOf(address)(uint256)" $ADDRESS | cut -d' ' -f1
+}
+
+readonly BALANCE_BEFORE=$(token_balance)
+readonly LOTS_BEFORE=$(lots_free)
+
+echo "Deposit 1 lot"
+cast send $BASE_TOKEN \
+    "approve(address,uint256)" $CONTRACT $ATOMS_PER_LOT \
+    --private-key $PRIVATE_KEY >/dev/null
+cast send $CONTRACT \
+    "0x$NUM_CALLS$DEPOSIT_ERC20_SELECTOR$ERC20_PAYLOAD_LEN${BASE_TOKEN#0x}${ADDRESS#0x}$LOTS" \
+    --private-key $PRIVATE_KEY >/dev/null
+
+[ "$(lots_free)" -eq $((LOTS_BEFORE + 1)) ] || fail "deposit did not credit 1 lot"
+[ "$(token_balance)" != "$BALANCE_BEFORE" ] || fail "deposit did not pull tokens"
+
+echo "Withdraw 1 lot"
+cast send $CONTRACT \
+    "0x$NUM_CALLS$WITHDRAW_ERC20_SELECTOR$ERC20_PAYLOAD_LEN${BASE_TOKEN#0x}${ADDRESS#0x}$LOTS" \
+    --private-key $PRIVATE_KEY >/dev/null
+
+[ "$(lots_free)" -eq "$LOTS_BEFORE" ] || fail "withdrawal did not debit 1 lot"
+[ "$(token_balance)" == "$BALANCE_BEFORE" ] || fail "withdrawal did not return tokens"
+
+echo "goblin-core e2e passed"
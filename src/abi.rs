@@ -0,0 +1,287 @@
+//! Solidity ABI compatibility
+//!
+//! Calls encoded with 4 byte keccak selectors are rewritten into a single call batch
+//! of the custom wire format and routed to the same handlers, so that `cast` and other
+//! ABI tooling can deposit and withdraw without a custom encoder.
+//!
+//! * The custom format starts with `header | selector | payload_len (u16 LE)`. Supported
+//!   ABI selectors have either a second byte above every custom selector or a fourth byte
+//!   above the high byte of any payload length, so the two formats can't be confused.
+//!
+//! * Arguments must be canonically encoded. Dirty upper bytes of an address or `uint64`
+//!   argument are rejected.
+//!
+//! * Getters keep their raw return layout and are not exposed through the ABI.
+//!
+//! * Enabled with the `abi` feature, since matching selectors grows the binary.
+//!
+use core::mem::MaybeUninit;
+
+use crate::{handler::*, MAX_ARGS_LEN};
+
+/// An ABI argument and where it goes in the params struct of the handler
+#[derive(Clone, Copy)]
+enum Arg {
+    /// `address`, written as 20 bytes
+    Address,
+
+    /// `uint64`, written as little endian lots
+    Lots,
+
+    /// Zero bytes inserted by `repr(C)` alignment. Consumes no ABI word.
+    Padding(usize),
+}
+
+struct AbiFunction {
+    selector: [u8; 4],
+    handler: u8,
+    args: &'static [Arg],
+}
+
+const FUNCTIONS: [AbiFunction; 4] = [
+    // keccak256('creditEth(address)') = 0xbef714dc
+    AbiFunction {
+        selector: [0xbe, 0xf7, 0x14, 0xdc],
+        handler: HANDLE_0_CREDIT_ETH,
+        args: &[Arg::Address],
+    },
+    // keccak256('creditErc20(address,address,uint64)') = 0xb6e00523
+    AbiFunction {
+        selector: [0xb6, 0xe0, 0x05, 0x23],
+        handler: HANDLE_1_CREDIT_ERC20,
+        args: &[Arg::Address, Arg::Address, Arg::Lots],
+    },
+    // keccak256('withdrawEth(address,uint64)') = 0x056d489e
+    AbiFunction {
+        selector: [0x05, 0x6d, 0x48, 0x9e],
+        handler: HANDLE_2_WITHDRAW_ETH,
+        args: &[Arg::Address, Arg::Padding(4), Arg::Lots],
+    },
+    // keccak256('withdrawErc20(address,address,uint64)') = 0x5c445d55
+    AbiFunction {
+        selector: [0x5c, 0x44, 0x5d, 0x55],
+        handler: HANDLE_3_WITHDRAW_ERC20,
+        args: &[Arg::Address, Arg::Address, Arg::Lots],
+    },
+];
+
+/// Longest ABI call among [`FUNCTIONS`]
+const MAX_ABI_LEN: usize = 4 + 32 * 3;
+
+pub enum Translation {
+    /// Not an ABI call. The input is in the custom format.
+    NotAbi,
+
+    /// A supported ABI call with malformed arguments
+    Invalid,
+
+    /// The ABI call was rewritten in place into a batch of this length
+    Batch(usize),
+}
+
+/// Rewrite a supported ABI call in `input` into a single call batch
+pub fn translate(input: &mut [u8; MAX_ARGS_LEN], len: usize) -> Translation {
+    if len < 4 {
+        return Translation::NotAbi;
+    }
+
+    let Some(function) = FUNCTIONS
+        .iter()
+        .find(|function| input[0..4] == function.selector)
+    else {
+        return Translation::NotAbi;
+    };
+
+    let num_words = function
+        .args
+        .iter()
+        .filter(|arg| !matches!(arg, Arg::Padding(_)))
+        .count();
+    if len != 4 + 32 * num_words {
+        return Translation::Invalid;
+    }
+
+    // Copy the arguments out since the batch is written over them
+    let mut abi_maybe = MaybeUninit::<[u8; MAX_ABI_LEN]>::uninit();
+    let abi = unsafe {
+        core::ptr::copy_nonoverlapping(input.as_ptr(), abi_maybe.as_mut_ptr() as *mut u8, len);
+        abi_maybe.assume_init_ref()
+    };
+
+    // header | selector | payload_len, written once the payload length is known
+    let mut offset = 4;
+    let mut word = 4;
+    for arg in function.args {
+        match arg {
+            Arg::Address => {
+                if abi[word..word + 12] != [0u8; 12] {
+                    return Translation::Invalid;
+                }
+                input[offset..offset + 20].copy_from_slice(&abi[word + 12..word + 32]);
+                offset += 20;
+                word += 32;
+            }
+            Arg::Lots => {
+                if abi[word..word + 24] != [0u8; 24] {
+                    return Translation::Invalid;
+                }
+                input[offset..offset + 8].copy_from_slice(&abi[word + 24..word + 32]);
+                input[offset..offset + 8].reverse();
+                offset += 8;
+                word += 32;
+            }
+            Arg::Padding(padding) => {
+                input[offset..offset + padding].fill(0);
+                offset += padding;
+            }
+        }
+    }
+
+    let payload_len = ((offset - 4) as u16).to_le_bytes();
+    input[0] = 1;
+    input[1] = function.handler;
+    input[2] = payload_len[0];
+    input[3] = payload_len[1];
+
+    Translation::Batch(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use tiny_keccak::{Hasher, Keccak};
+
+    use super::*;
+    use crate::{
+        getter::read_trader_token_state,
+        hostio::*,
+        interface::NUM_SELECTORS,
+        state::{TraderTokenKey, TraderTokenState},
+        types::NATIVE_TOKEN,
+        user_entrypoint,
+    };
+
+    const TRADER: [u8; 20] = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    fn selector(signature: &str) -> [u8; 4] {
+        let mut hasher = Keccak::v256();
+        hasher.update(signature.as_bytes());
+        let mut hash = [0u8; 32];
+        hasher.finalize(&mut hash);
+        hash[..4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        let signatures = [
+            "creditEth(address)",
+            "creditErc20(address,address,uint64)",
+            "withdrawEth(address,uint64)",
+            "withdrawErc20(address,address,uint64)",
+        ];
+        for (function, signature) in FUNCTIONS.iter().zip(signatures) {
+            assert_eq!(function.selector, selector(signature), "{}", signature);
+        }
+    }
+
+    #[test]
+    fn test_selectors_dont_collide_with_custom_format() {
+        let max_payload_len_high_byte = (MAX_ARGS_LEN as u16).to_le_bytes()[1];
+        for function in &FUNCTIONS {
+            // Byte 1 is read as the first call's selector, bytes 2..4 as its payload length
+            assert!(
+                function.selector[1] as usize >= NUM_SELECTORS
+                    || function.selector[3] > max_payload_len_high_byte
+            );
+        }
+    }
+
+    #[test]
+    fn test_payload_lens_match_handlers() {
+        let payload_lens = [
+            HANDLE_0_PAYLOAD_LEN,
+            HANDLE_1_PAYLOAD_LEN,
+            HANDLE_2_PAYLOAD_LEN,
+            HANDLE_3_PAYLOAD_LEN,
+        ];
+        for (function, payload_len) in FUNCTIONS.iter().zip(payload_lens) {
+            let args_len: usize = function
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    Arg::Address => 20,
+                    Arg::Lots => 8,
+                    Arg::Padding(padding) => *padding,
+                })
+                .sum();
+            assert_eq!(args_len, payload_len);
+        }
+    }
+
+    fn abi_word(bytes: &[u8]) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[32 - bytes.len()..].copy_from_slice(bytes);
+        word
+    }
+
+    #[test]
+    fn test_translate_withdraw_eth() {
+        let mut input = [0u8; MAX_ARGS_LEN];
+        input[0..4].copy_from_slice(&FUNCTIONS[2].selector);
+        input[4..36].copy_from_slice(&abi_word(&TRADER));
+        input[36..68].copy_from_slice(&abi_word(&5u64.to_be_bytes()));
+
+        let Translation::Batch(len) = translate(&mut input, 68) else {
+            panic!("expected a batch");
+        };
+        assert_eq!(len, 4 + HANDLE_2_PAYLOAD_LEN);
+        assert_eq!(input[0..4], [1, HANDLE_2_WITHDRAW_ETH, 32, 0]);
+        assert_eq!(input[4..24], TRADER);
+        assert_eq!(input[24..28], [0u8; 4]);
+        assert_eq!(input[28..36], 5u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_translate_rejects_dirty_arguments() {
+        let mut input = [0u8; MAX_ARGS_LEN];
+        input[0..4].copy_from_slice(&FUNCTIONS[2].selector);
+        input[4..36].copy_from_slice(&abi_word(&TRADER));
+        input[36..68].copy_from_slice(&abi_word(&5u64.to_be_bytes()));
+        input[36] = 1;
+
+        assert!(matches!(translate(&mut input, 68), Translation::Invalid));
+
+        // Wrong length
+        assert!(matches!(translate(&mut input, 67), Translation::Invalid));
+    }
+
+    #[test]
+    fn test_custom_format_is_not_abi() {
+        let mut input = [0u8; MAX_ARGS_LEN];
+        input[0..4].copy_from_slice(&[1, HANDLE_0_CREDIT_ETH, 20, 0]);
+
+        assert!(matches!(translate(&mut input, 24), Translation::NotAbi));
+    }
+
+    #[test]
+    fn test_credit_eth_through_abi() {
+        crate::registry::set_operator_sender(&TRADER);
+
+        set_msg_value(hex!(
+            "00000000000000000000000000000000000000000000000000000000000F4240"
+        ));
+
+        let mut test_args = FUNCTIONS[0].selector.to_vec();
+        test_args.extend_from_slice(&abi_word(&TRADER));
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 0);
+
+        let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+            trader: TRADER,
+            token: NATIVE_TOKEN,
+        });
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, 1);
+    }
+}
@@ -1,10 +1,21 @@
 use core::mem::MaybeUninit;
 
-use crate::{call_contract, quantities::Atoms, read_return_data, types::Address};
+use crate::{
+    call_contract, quantities::Atoms, read_return_data, static_call_contract, types::Address,
+};
 
 // keccak256('transferFrom(address,address,uint256)') = 0x23b872dd
 const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
 
+// keccak256('transfer(address,uint256)') = 0xa9059cbb
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+// keccak256('balanceOf(address)') = 0x70a08231
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+
+// keccak256('allowance(address,address)') = 0xdd62ed3e
+const ALLOWANCE_SELECTOR: [u8; 4] = [0xdd, 0x62, 0xed, 0x3e];
+
 pub fn transfer_from(
     contract: &Address,
     sender: &Address,
@@ -28,6 +39,109 @@ pub fn transfer_from(
     let amount_as_be_bytes: &[u8; 32] = unsafe { &*(amount.0.as_ptr() as *const [u8; 32]) };
     calldata[68..100].copy_from_slice(amount_as_be_bytes);
 
+    call_and_check_result(contract, &calldata)
+}
+
+pub fn transfer(contract: &Address, recipient: &Address, amount: &Atoms) -> u8 {
+    let mut calldata = [0u8; 4 + 32 * 2];
+
+    calldata[0..4].copy_from_slice(&TRANSFER_SELECTOR);
+
+    // 4..36: recipient address
+    // 4..16 are zeroes, 16..36 holds 20 byte address
+    calldata[16..36].copy_from_slice(recipient);
+
+    // 36..68: amount to transfer
+    // This is a 32 byte value
+    let amount_as_be_bytes: &[u8; 32] = unsafe { &*(amount.0.as_ptr() as *const [u8; 32]) };
+    calldata[36..68].copy_from_slice(amount_as_be_bytes);
+
+    call_and_check_result(contract, &calldata)
+}
+
+/// Call transferFrom() and return the amount `recipient` actually received, measured as
+/// the difference of its balance before and after the transfer.
+///
+/// Fee-on-transfer tokens deliver less than `amount`. Returns None if any call fails.
+pub fn transfer_from_measured(
+    contract: &Address,
+    sender: &Address,
+    recipient: &Address,
+    amount: &Atoms,
+) -> Option<Atoms> {
+    let balance_before = balance_of(contract, recipient)?;
+
+    if transfer_from(contract, sender, recipient, amount) != 0 {
+        return None;
+    }
+
+    let balance_after = balance_of(contract, recipient)?;
+    balance_after.checked_sub(&balance_before)
+}
+
+/// Read the token balance of `owner`. Returns None if the call fails or returns
+/// less than 32 bytes.
+pub fn balance_of(contract: &Address, owner: &Address) -> Option<Atoms> {
+    let mut calldata = [0u8; 4 + 32];
+
+    calldata[0..4].copy_from_slice(&BALANCE_OF_SELECTOR);
+
+    // 4..36: owner address
+    // 4..16 are zeroes, 16..36 holds 20 byte address
+    calldata[16..36].copy_from_slice(owner);
+
+    static_call_u256(contract, &calldata)
+}
+
+/// Read the amount `spender` can transfer from `owner`. Returns None if the call fails
+/// or returns less than 32 bytes.
+pub fn allowance(contract: &Address, owner: &Address, spender: &Address) -> Option<Atoms> {
+    let mut calldata = [0u8; 4 + 32 * 2];
+
+    calldata[0..4].copy_from_slice(&ALLOWANCE_SELECTOR);
+
+    // 4..36: owner address
+    // 4..16 are zeroes, 16..36 holds 20 byte address
+    calldata[16..36].copy_from_slice(owner);
+
+    // 36..68: spender address
+    // 36..48 are zeroes, 48..68 holds 20 byte address
+    calldata[48..68].copy_from_slice(spender);
+
+    static_call_u256(contract, &calldata)
+}
+
+/// Static call a view returning a uint256. Returns None if the call fails or returns
+/// less than 32 bytes.
+fn static_call_u256(contract: &Address, calldata: &[u8]) -> Option<Atoms> {
+    let return_data_len: &mut usize = &mut 0;
+
+    let call_result = unsafe {
+        static_call_contract(
+            contract.as_ptr(),
+            calldata.as_ptr(),
+            calldata.len(),
+            200_000, // 200k gas. We need to explicitly specify gas else, tx fails
+            return_data_len,
+        )
+    };
+
+    if call_result != 0 || *return_data_len < 32 {
+        return None;
+    }
+
+    // The uint256 return value is big endian, same as Atoms
+    let mut value_maybe = MaybeUninit::<Atoms>::uninit();
+    let value = unsafe {
+        read_return_data(value_maybe.as_mut_ptr() as *mut u8, 0, 32);
+        value_maybe.assume_init()
+    };
+
+    Some(value)
+}
+
+/// Call an ERC20 function that returns a boolean. Returns 0 on success and 1 on failure.
+fn call_and_check_result(contract: &Address, calldata: &[u8]) -> u8 {
     let value = Atoms::default();
     let return_data_len: &mut usize = &mut 0;
 
@@ -42,7 +156,7 @@ pub fn transfer_from(
         )
     };
 
-    // The original ERC20 spec transferFrom() returns false if the transfer fails. However
+    // The original ERC20 spec returns false if the transfer fails. However
     // Openzepplin and modern ERC20 token implementations will revert instead of returning false.
     // We need to handle both cases.
     if call_result != 0 {
@@ -79,6 +193,70 @@ pub fn transfer_from(
 mod tests {
     use hex_literal::hex;
 
+    use super::*;
+    use crate::{push_return_data, set_return_data, test_fixtures::encode_u256};
+
+    #[test]
+    fn test_balance_of() {
+        let token = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+        let owner = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+        let mut return_data = vec![0u8; 32];
+        return_data[31] = 5;
+        return_data[30] = 1;
+        push_return_data(return_data);
+
+        let balance = balance_of(&token, &owner).unwrap();
+        assert_eq!(balance.0, [0, 0, 0, 261u64.swap_bytes()]);
+
+        // Too short to hold a uint256
+        set_return_data(vec![1]);
+        assert!(balance_of(&token, &owner).is_none());
+    }
+
+    #[test]
+    fn test_allowance() {
+        let token = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+        let owner = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        let spender = hex!("a6e41ffd769491a42a6e5ce453259b93983a22ef");
+
+        push_return_data(encode_u256(7));
+        let amount = allowance(&token, &owner, &spender).unwrap();
+        assert_eq!(amount.0, [0, 0, 0, 7u64.swap_bytes()]);
+
+        set_return_data(vec![]);
+        assert!(allowance(&token, &owner, &spender).is_none());
+    }
+
+    #[test]
+    fn test_transfer_from_measured() {
+        let token = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+        let sender = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        let recipient = hex!("a6e41ffd769491a42a6e5ce453259b93983a22ef");
+
+        // Balance before, transferFrom() result and balance after. 1% fee is charged.
+        push_return_data(encode_u256(500));
+        push_return_data(encode_u256(1));
+        push_return_data(encode_u256(500 + 990_000));
+
+        let amount = Atoms([0, 0, 0, 1_000_000u64.swap_bytes()]);
+        let received = transfer_from_measured(&token, &sender, &recipient, &amount).unwrap();
+        assert_eq!(received.0, [0, 0, 0, 990_000u64.swap_bytes()]);
+    }
+
+    #[test]
+    fn test_transfer_from_measured_fails_if_transfer_fails() {
+        let token = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+        let sender = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        let recipient = hex!("a6e41ffd769491a42a6e5ce453259b93983a22ef");
+
+        push_return_data(encode_u256(500));
+        push_return_data(encode_u256(0));
+
+        let amount = Atoms([0, 0, 0, 1_000_000u64.swap_bytes()]);
+        assert!(transfer_from_measured(&token, &sender, &recipient, &amount).is_none());
+    }
+
     #[test]
     fn test_amount_encoding() {
         let amount = hex!("00000001");
@@ -0,0 +1,22 @@
+use crate::{call_contract, quantities::Atoms, types::Address};
+
+/// Send `amount` wei to `recipient` with empty calldata.
+///
+/// Returns 0 on success and 1 if the call reverted.
+pub fn transfer(recipient: &Address, amount: &Atoms) -> u8 {
+    let return_data_len: &mut usize = &mut 0;
+
+    let call_result = unsafe {
+        call_contract(
+            recipient.as_ptr(),
+            core::ptr::null(),
+            0,
+            amount.0.as_ptr() as *const u8,
+            200_000, // 200k gas. We need to explicitly specify gas else, tx fails
+            return_data_len,
+        )
+    };
+
+    // call_contract() returns a non-zero status if the call reverted
+    (call_result != 0) as u8
+}
@@ -0,0 +1,78 @@
+//! Logs for balance changes that move tokens in or out of the contract.
+//!
+//! Indexers can reconcile trader balances from these logs without tracing token transfers.
+//! Lots are ABI encoded as `uint64` in big endian, unlike the little endian wire format.
+
+use crate::{emit_log, quantities::Lots, types::Address};
+
+// keccak256('Deposit(address,address,uint64)')
+pub const DEPOSIT_TOPIC: [u8; 32] = [
+    0x1f, 0xaa, 0x52, 0x51, 0x43, 0xd1, 0xba, 0x84, 0x28, 0x0a, 0xe1, 0x25, 0x1d, 0x86, 0x22, 0xe2,
+    0xe4, 0x81, 0xb7, 0x71, 0xa6, 0x41, 0xc5, 0x9c, 0xa8, 0x8a, 0x2e, 0x2e, 0x8c, 0x15, 0x9e, 0xcb,
+];
+
+// keccak256('Withdraw(address,address,address,uint64)')
+pub const WITHDRAW_TOPIC: [u8; 32] = [
+    0x04, 0x8a, 0xb0, 0xf9, 0xda, 0x3d, 0x40, 0x9f, 0x24, 0xb6, 0x51, 0x07, 0xd0, 0xcb, 0xaa, 0x67,
+    0xac, 0x39, 0x7d, 0x53, 0xbb, 0xc1, 0xc9, 0xc9, 0x77, 0xf9, 0x5c, 0x14, 0xd9, 0xc5, 0x75, 0xf9,
+];
+
+/// Write an address as a left padded 32 byte word
+fn write_address(word: &mut [u8], address: &Address) {
+    word[12..32].copy_from_slice(address);
+}
+
+/// Write lots as a big endian 32 byte word
+fn write_lots(word: &mut [u8], lots: Lots) {
+    word[24..32].copy_from_slice(&lots.0.to_be_bytes());
+}
+
+/// Emit `Deposit(address indexed trader, address indexed token, uint64 lots)`
+pub fn emit_deposit(trader: &Address, token: &Address, lots: Lots) {
+    // 3 topics followed by 1 data word
+    let mut log = [0u8; 32 * 4];
+
+    log[0..32].copy_from_slice(&DEPOSIT_TOPIC);
+    write_address(&mut log[32..64], trader);
+    write_address(&mut log[64..96], token);
+    write_lots(&mut log[96..128], lots);
+
+    unsafe { emit_log(log.as_ptr(), log.len(), 3) };
+}
+
+/// Emit `Withdraw(address indexed trader, address indexed token, address recipient, uint64 lots)`
+pub fn emit_withdraw(trader: &Address, token: &Address, recipient: &Address, lots: Lots) {
+    // 3 topics followed by 2 data words
+    let mut log = [0u8; 32 * 5];
+
+    log[0..32].copy_from_slice(&WITHDRAW_TOPIC);
+    write_address(&mut log[32..64], trader);
+    write_address(&mut log[64..96], token);
+    write_address(&mut log[96..128], recipient);
+    write_lots(&mut log[128..160], lots);
+
+    unsafe { emit_log(log.as_ptr(), log.len(), 3) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_keccak::{Hasher, Keccak};
+
+    fn keccak256(input: &[u8]) -> [u8; 32] {
+        let mut hasher = Keccak::v256();
+        hasher.update(input);
+        let mut output = [0u8; 32];
+        hasher.finalize(&mut output);
+        output
+    }
+
+    #[test]
+    fn test_topics_match_signatures() {
+        assert_eq!(DEPOSIT_TOPIC, keccak256(b"Deposit(address,address,uint64)"));
+        assert_eq!(
+            WITHDRAW_TOPIC,
+            keccak256(b"Withdraw(address,address,address,uint64)")
+        );
+    }
+}
@@ -34,6 +34,7 @@ pub fn read_trader_token_state(trader_token_key: &TraderTokenKey) -> Vec<u8> {
     let num_calls: u8 = 1;
     test_args.push(num_calls);
     test_args.push(GET_10_TRADER_TOKEN_STATE);
+    test_args.extend_from_slice(&(GET_10_PAYLOAD_LEN as u16).to_le_bytes());
 
     let payload_bytes: &[u8] = unsafe {
         core::slice::from_raw_parts(
@@ -0,0 +1,198 @@
+use crate::{
+    interface::{fields_len, Field, FieldKind, NUM_SELECTORS, SELECTORS},
+    write_result,
+};
+
+pub const GET_11_INTERFACE_VERSION: u8 = 11;
+pub const GET_11_PAYLOAD_LEN: usize = 0;
+
+/// Bumped whenever a selector is added or a payload or return layout changes
+pub const INTERFACE_VERSION: u16 = 9;
+
+/// Length of an entry in [`MANIFEST`] before its fields
+const ENTRY_HEADER_LEN: usize = 9;
+
+/// Length of a field in [`MANIFEST`]
+const FIELD_LEN: usize = 3;
+
+/// Length of the manifest returned by [`get_11_interface_version`]
+pub const MANIFEST_LEN: usize = manifest_len();
+
+const fn manifest_len() -> usize {
+    let mut len = 3;
+    let mut i = 0;
+    while i < NUM_SELECTORS {
+        let entry = &SELECTORS[i];
+        len += ENTRY_HEADER_LEN + (entry.payload.len() + entry.returns.len()) * FIELD_LEN;
+        i += 1;
+    }
+    len
+}
+
+/// The interface manifest, built at compile time from [`SELECTORS`]
+///
+/// # Layout
+///
+/// ```text
+/// version (u16, little endian) | num_selectors (1 byte) | entry_0 | entry_1 | ...
+///
+/// entry = selector (1 byte) | writes_storage (1 byte) | payable (1 byte)
+///     | payload_len (u16, little endian) | return_len (u16, little endian)
+///     | num_payload_fields (1 byte) | num_return_fields (1 byte)
+///     | payload_field_0 | ... | return_field_0 | ...
+///
+/// field = kind (1 byte, see [`FieldKind`]) | len (u16, little endian)
+/// ```
+pub const MANIFEST: [u8; MANIFEST_LEN] = build_manifest();
+
+/// Length of a field. The manifest is the only field of its own length.
+const fn field_len(field: &Field) -> usize {
+    match field.kind {
+        FieldKind::Bytes => MANIFEST_LEN,
+        _ => field.len,
+    }
+}
+
+const fn write_u16(manifest: &mut [u8; MANIFEST_LEN], offset: usize, value: usize) {
+    let bytes = (value as u16).to_le_bytes();
+    manifest[offset] = bytes[0];
+    manifest[offset + 1] = bytes[1];
+}
+
+const fn write_fields(
+    manifest: &mut [u8; MANIFEST_LEN],
+    mut offset: usize,
+    fields: &[Field],
+) -> usize {
+    let mut i = 0;
+    while i < fields.len() {
+        manifest[offset] = fields[i].kind as u8;
+        write_u16(manifest, offset + 1, field_len(&fields[i]));
+        offset += FIELD_LEN;
+        i += 1;
+    }
+    offset
+}
+
+const fn build_manifest() -> [u8; MANIFEST_LEN] {
+    let mut manifest = [0u8; MANIFEST_LEN];
+
+    write_u16(&mut manifest, 0, INTERFACE_VERSION as usize);
+    manifest[2] = NUM_SELECTORS as u8;
+
+    let mut offset = 3;
+    let mut i = 0;
+    while i < NUM_SELECTORS {
+        let entry = &SELECTORS[i];
+
+        let mut return_len = fields_len(entry.returns);
+        let mut j = 0;
+        while j < entry.returns.len() {
+            if let FieldKind::Bytes = entry.returns[j].kind {
+                return_len += MANIFEST_LEN;
+            }
+            j += 1;
+        }
+
+        manifest[offset] = entry.selector;
+        manifest[offset + 1] = entry.writes_storage as u8;
+        manifest[offset + 2] = entry.payable as u8;
+        write_u16(&mut manifest, offset + 3, entry.payload_len);
+        write_u16(&mut manifest, offset + 5, return_len);
+        manifest[offset + 7] = entry.payload.len() as u8;
+        manifest[offset + 8] = entry.returns.len() as u8;
+
+        offset = write_fields(&mut manifest, offset + ENTRY_HEADER_LEN, entry.payload);
+        offset = write_fields(&mut manifest, offset, entry.returns);
+        i += 1;
+    }
+
+    manifest
+}
+
+/// Returns the interface version and the layout of every selector, so that clients can
+/// check the wire format before sending calls
+pub fn get_11_interface_version(_payload: &[u8]) -> i32 {
+    unsafe {
+        write_result(MANIFEST.as_ptr(), MANIFEST_LEN);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{set_test_args, user_entrypoint};
+
+    /// Returns the manifest entry of `selector`
+    fn find_entry(manifest: &[u8], selector: u8) -> &[u8] {
+        let mut offset = 3;
+        loop {
+            let num_fields = (manifest[offset + 7] + manifest[offset + 8]) as usize;
+            let entry_len = ENTRY_HEADER_LEN + num_fields * FIELD_LEN;
+            if manifest[offset] == selector {
+                return &manifest[offset..offset + entry_len];
+            }
+            offset += entry_len;
+        }
+    }
+
+    #[test]
+    fn test_get_interface_version() {
+        let mut test_args: Vec<u8> = vec![1, GET_11_INTERFACE_VERSION];
+        test_args.extend_from_slice(&(GET_11_PAYLOAD_LEN as u16).to_le_bytes());
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 0);
+
+        let result = crate::get_test_result();
+        assert_eq!(result.len(), MANIFEST_LEN);
+        assert_eq!(
+            u16::from_le_bytes([result[0], result[1]]),
+            INTERFACE_VERSION
+        );
+        assert_eq!(result[2] as usize, NUM_SELECTORS);
+
+        // Credit ETH reads msg_value
+        assert_eq!(
+            find_entry(&result, 0),
+            [0, 1, 1, 20, 0, 0, 0, 1, 0, 0, 20, 0]
+        );
+
+        // Withdraw ERC20: token, recipient and lots
+        assert_eq!(
+            find_entry(&result, 3),
+            [3, 1, 0, 48, 0, 0, 0, 3, 0, 0, 20, 0, 0, 20, 0, 1, 8, 0]
+        );
+
+        // Trader token state: low then high words of lots locked and lots free
+        assert_eq!(
+            find_entry(&result, 10),
+            [
+                10, 0, 0, 40, 0, 32, 0, 2, 4, 0, 20, 0, 0, 20, 0, 1, 8, 0, 1, 8, 0, 1, 8, 0, 1, 8,
+                0
+            ]
+        );
+
+        // The manifest reports its own length
+        let manifest_len = (MANIFEST_LEN as u16).to_le_bytes();
+        assert_eq!(
+            find_entry(&result, 11),
+            [
+                11,
+                0,
+                0,
+                0,
+                0,
+                manifest_len[0],
+                manifest_len[1],
+                0,
+                1,
+                5,
+                manifest_len[0],
+                manifest_len[1]
+            ]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    account_balance, erc20,
+    quantities::Atoms,
+    state::{SlotState, TraderTokenKey, TraderTokenState},
+    types::NATIVE_TOKEN,
+    write_result, ADDRESS,
+};
+
+pub const GET_12_TOKEN_PREFLIGHT: u8 = 12;
+pub const GET_12_PAYLOAD_LEN: usize = core::mem::size_of::<TraderTokenKey>();
+
+/// Allowance reported for ETH, which is deposited with `msg_value` and needs no approval
+const NATIVE_ALLOWANCE: Atoms = Atoms([u64::MAX; 4]);
+
+/// Result of [`get_12_token_preflight`]
+#[repr(C)]
+pub struct TokenPreflight {
+    /// Wallet balance of the trader, in big endian
+    pub balance: Atoms,
+
+    /// Amount this contract can pull from the trader, in big endian
+    pub allowance: Atoms,
+
+    /// Lots already credited to the trader
+    pub trader_token_state: TraderTokenState,
+}
+
+/// Returns the wallet balance and allowance of a trader next to its credited lots, so
+/// that clients can show how much can be deposited with a single call
+///
+/// * ERC20 balance and allowance are read with static calls to the token. Fails if
+///   either call fails.
+///
+/// * For ETH the account balance is returned with an unlimited allowance.
+///
+pub fn get_12_token_preflight(payload: &[u8]) -> i32 {
+    let key = unsafe { &*(payload.as_ptr() as *const TraderTokenKey) };
+
+    // Zeroed so that every field is initialized before it is overwritten
+    let mut preflight_maybe = MaybeUninit::<TokenPreflight>::zeroed();
+    let preflight = unsafe { preflight_maybe.assume_init_mut() };
+
+    if key.token == NATIVE_TOKEN {
+        unsafe {
+            account_balance(
+                key.trader.as_ptr(),
+                preflight.balance.0.as_mut_ptr() as *mut u8,
+            );
+        }
+        preflight.allowance = NATIVE_ALLOWANCE;
+    } else {
+        let Some(balance) = erc20::balance_of(&key.token, &key.trader) else {
+            return 1;
+        };
+        let Some(allowance) = erc20::allowance(&key.token, &key.trader, &ADDRESS) else {
+            return 1;
+        };
+        preflight.balance = balance;
+        preflight.allowance = allowance;
+    }
+
+    unsafe {
+        // Load the slot in place. MaybeUninit<T> has the same layout as T.
+        let trader_token_state_maybe = &mut *(&mut preflight.trader_token_state
+            as *mut TraderTokenState
+            as *mut MaybeUninit<TraderTokenState>);
+        TraderTokenState::load(key, trader_token_state_maybe);
+
+        write_result(
+            preflight as *const TokenPreflight as *const u8,
+            core::mem::size_of::<TokenPreflight>(),
+        );
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use hex_literal::hex;
+
+    use super::*;
+    use crate::{
+        get_test_result, push_return_data, quantities::Lots, set_account_balance, set_test_args,
+        test_fixtures::encode_u256, types::Address, user_entrypoint,
+    };
+
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    fn preflight(token: Address) -> (i32, Vec<u8>) {
+        let mut test_args: Vec<u8> = vec![1, GET_12_TOKEN_PREFLIGHT];
+        test_args.extend_from_slice(&(GET_12_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&TRADER);
+        test_args.extend_from_slice(&token);
+        set_test_args(test_args.clone());
+
+        let result = user_entrypoint(test_args.len());
+        (result, get_test_result())
+    }
+
+    #[test]
+    fn test_erc20_preflight() {
+        let key = &TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        };
+        let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+        unsafe {
+            let trader_token_state = TraderTokenState::load(key, &mut trader_token_state_maybe);
+            trader_token_state.lots_free = Lots(3);
+            trader_token_state.store(key);
+        }
+
+        // balanceOf, then allowance
+        push_return_data(encode_u256(5_000_000));
+        push_return_data(encode_u256(2_000_000));
+
+        let (result, data) = preflight(TOKEN);
+        assert_eq!(result, 0);
+        assert_eq!(data.len(), core::mem::size_of::<TokenPreflight>());
+
+        let preflight = unsafe { &*(data.as_ptr() as *const TokenPreflight) };
+        assert_eq!(preflight.balance.to_be_bytes()[..], encode_u256(5_000_000));
+        assert_eq!(
+            preflight.allowance.to_be_bytes()[..],
+            encode_u256(2_000_000)
+        );
+        assert_eq!(preflight.trader_token_state.lots_free.0, 3);
+        assert_eq!(preflight.trader_token_state.lots_locked.0, 0);
+    }
+
+    #[test]
+    fn test_erc20_preflight_fails_if_token_call_fails() {
+        push_return_data(encode_u256(5_000_000));
+        push_return_data(vec![]);
+
+        let (result, _) = preflight(TOKEN);
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    fn test_eth_preflight() {
+        let mut balance = [0u8; 32];
+        balance[31] = 9;
+        set_account_balance(balance);
+
+        let (result, data) = preflight(NATIVE_TOKEN);
+        assert_eq!(result, 0);
+
+        let preflight = unsafe { &*(data.as_ptr() as *const TokenPreflight) };
+        assert_eq!(*preflight.balance.to_be_bytes(), balance);
+        assert_eq!(*preflight.allowance.to_be_bytes(), [0xff; 32]);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod get_10_trader_token_state;
+pub mod get_11_interface_version;
+pub mod get_12_token_preflight;
 
 pub use get_10_trader_token_state::*;
+pub use get_11_interface_version::*;
+pub use get_12_token_preflight::*;
@@ -0,0 +1,128 @@
+//! Guards for state mutating handlers
+//!
+//! * A reentrancy lock held in transient storage while a handler runs. Handlers calling
+//!   tokens or sending ETH can't be re-entered by the callee.
+//!
+//! * An optional operator allowlist, enabled with the `operator-allowlist` feature. When
+//!   enabled only addresses in the [`crate::registry::OPERATOR`] list can credit
+//!   balances, e.g. the paired market contract. Traders can opt into the same check for
+//!   credits to their own balances with [`crate::state::TraderConfig`].
+//!
+//! * A market allowlist. Only addresses in the [`crate::registry::MARKET`] list can lock,
+//!   unlock and settle trader funds. This is always enforced since these calls move funds
+//!   of other traders.
+//!
+use core::mem::MaybeUninit;
+
+use crate::{
+    state::{SlotState, TraderConfig, TraderConfigKey},
+    transient_load_bytes32, transient_store_bytes32,
+    types::Address,
+};
+
+/// Transient slot of the reentrancy lock. Transient storage has its own namespace so
+/// this can't collide with persistent slots.
+const REENTRANCY_LOCK_KEY: [u8; 32] = [0u8; 32];
+
+const UNLOCKED: [u64; 4] = [0; 4];
+const LOCKED: [u64; 4] = [0, 0, 0, 1];
+
+/// Acquire the reentrancy lock. Returns 1 if the lock is already held.
+pub fn lock() -> u8 {
+    let mut lock_maybe = MaybeUninit::<[u64; 4]>::uninit();
+    let lock = unsafe {
+        transient_load_bytes32(
+            REENTRANCY_LOCK_KEY.as_ptr(),
+            lock_maybe.as_mut_ptr() as *mut u8,
+        );
+        lock_maybe.assume_init_ref()
+    };
+
+    if *lock != UNLOCKED {
+        return 1;
+    }
+
+    unsafe {
+        transient_store_bytes32(REENTRANCY_LOCK_KEY.as_ptr(), LOCKED.as_ptr() as *const u8);
+    }
+    0
+}
+
+/// Release the reentrancy lock
+pub fn unlock() {
+    unsafe {
+        transient_store_bytes32(REENTRANCY_LOCK_KEY.as_ptr(), UNLOCKED.as_ptr() as *const u8);
+    }
+}
+
+/// Returns 1 if `msg_sender` is not an allowed operator. Always succeeds if the
+/// `operator-allowlist` feature is disabled.
+#[cfg(feature = "operator-allowlist")]
+pub fn check_operator() -> u8 {
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        crate::msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    !crate::registry::is_registered(sender, crate::registry::OPERATOR) as u8
+}
+
+/// Returns 1 if `msg_sender` is not an allowed operator. Always succeeds if the
+/// `operator-allowlist` feature is disabled.
+#[cfg(not(feature = "operator-allowlist"))]
+#[inline(always)]
+pub fn check_operator() -> u8 {
+    0
+}
+
+/// Returns 1 if `recipient` enabled the operator allowlist in its [`TraderConfig`] and
+/// `msg_sender` is not in the [`crate::registry::OPERATOR`] list
+pub fn check_recipient_operator(recipient: &Address) -> u8 {
+    let mut trader_config_maybe = MaybeUninit::<TraderConfig>::uninit();
+    let trader_config = unsafe {
+        TraderConfig::load(
+            &TraderConfigKey { trader: *recipient },
+            &mut trader_config_maybe,
+        )
+    };
+    if trader_config.operator_allowlist == 0 {
+        return 0;
+    }
+
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        crate::msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    !crate::registry::is_registered(sender, crate::registry::OPERATOR) as u8
+}
+
+/// Returns 1 if `msg_sender` is not in the [`crate::registry::MARKET`] list
+pub fn check_market() -> u8 {
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        crate::msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    !crate::registry::is_registered(sender, crate::registry::MARKET) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_and_unlock() {
+        assert_eq!(lock(), 0);
+
+        // Re-entrant acquire fails
+        assert_eq!(lock(), 1);
+
+        unlock();
+        assert_eq!(lock(), 0);
+        unlock();
+    }
+}
@@ -1,10 +1,10 @@
 use core::mem::MaybeUninit;
 
 use crate::{
-    msg_value,
-    quantities::{Atoms, Lots},
+    events::emit_deposit,
+    guard, msg_value,
+    quantities::Atoms,
     state::{SlotState, TraderTokenKey, TraderTokenState},
-    storage_flush_cache,
     types::{Address, NATIVE_TOKEN},
 };
 
@@ -18,22 +18,37 @@ pub const HANDLE_0_PAYLOAD_LEN: usize = core::mem::size_of::<Address>();
 /// * The address is encoded in `payload`. The client call encodes the data such that we obtain
 /// the big endian result in a slice without need of any processing.
 ///
+/// * Fails if the recipient only accepts credits from operators and `msg_sender` is not
+///   one, see [`crate::state::TraderConfig`].
+///
+/// * Fails if the free balance would overflow.
+///
+/// * Emits a `Deposit` log for the credited lots.
+///
 /// # Example
 ///
 /// ```
 /// cast send 0xa6e41ffd769491a42a6e5ce453259b93983a22ef \
-///   0x003f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E \
+///   0x010014003f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E \
 ///   --value 1000000wei \
 ///   --rpc-url http://127.0.0.1:8547 \
 ///   --private-key 0xb6b15c8cb491557369f3c7d2c287b053eb229daa9c22138887752191c9520
 /// ```
 ///
-/// * After removing selector `00` we're left with payload `3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E`
+/// * After removing header `01`, selector `00` and payload length `1400` (20 in little endian)
+///   we're left with payload `3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E`
 /// * This payload is decoded as [0x3f, 0x1E, ..., 0E]
 /// * The address is already in big endian
 ///
 pub fn handle_0_credit_eth(payload: &[u8]) -> i32 {
+    if guard::check_operator() != 0 {
+        return 1;
+    }
+
     let recipient: &Address = unsafe { &*(payload.as_ptr() as *const Address) };
+    if guard::check_recipient_operator(recipient) != 0 {
+        return 1;
+    }
 
     // Amount of ETH in, in 64-bit chunks, in big endian encoding
     let mut amount_in_maybe = MaybeUninit::<Atoms>::uninit();
@@ -41,7 +56,9 @@ pub fn handle_0_credit_eth(payload: &[u8]) -> i32 {
         msg_value(amount_in_maybe.as_mut_ptr() as *mut u8);
         amount_in_maybe.assume_init_ref()
     };
-    let lots = Lots::from(amount_in);
+    let Some((lots, _dust)) = amount_in.split_lots() else {
+        return 1;
+    };
 
     let key = &TraderTokenKey {
         trader: *recipient,
@@ -50,12 +67,15 @@ pub fn handle_0_credit_eth(payload: &[u8]) -> i32 {
 
     let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
     let trader_token_state = unsafe { TraderTokenState::load(key, &mut trader_token_state_maybe) };
-    trader_token_state.lots_free += lots;
+    let Some(free_balance) = trader_token_state.free_balance().checked_add_lots(lots) else {
+        return 1;
+    };
+    trader_token_state.set_free_balance(free_balance);
 
     unsafe {
         trader_token_state.store(key);
-        storage_flush_cache(true);
     }
+    emit_deposit(recipient, &NATIVE_TOKEN, lots);
 
     0
 }
@@ -65,12 +85,17 @@ mod tests {
     use super::*;
     use hex_literal::hex;
 
-    use crate::{getter::read_trader_token_state, set_msg_value, set_test_args, user_entrypoint};
+    use crate::{
+        get_logs, getter::read_trader_token_state, quantities::LotBalance, set_msg_value,
+        set_test_args, test_fixtures::set_free_balance, user_entrypoint,
+    };
 
     use super::HANDLE_0_CREDIT_ETH;
 
     #[test]
     pub fn test_deposit() {
+        crate::registry::set_operator_sender(&hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E"));
+
         // Set msg.value to 10^6 in big endian
         let msg_value = hex!("00000000000000000000000000000000000000000000000000000000000F4240");
         set_msg_value(msg_value);
@@ -80,6 +105,7 @@ mod tests {
         let num_calls: u8 = 1;
         test_args.push(num_calls);
         test_args.push(HANDLE_0_CREDIT_ETH);
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
 
         let recipient = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
         test_args.extend_from_slice(&recipient);
@@ -108,5 +134,78 @@ mod tests {
 
         assert_eq!(trader_token_state.lots_free.0, 1);
         assert_eq!(trader_token_state.lots_locked.0, 0);
+
+        // Check Deposit log
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics[0], crate::events::DEPOSIT_TOPIC);
+        assert_eq!(topics[1][12..], recipient);
+        assert_eq!(topics[2][12..], NATIVE_TOKEN);
+        assert_eq!(data[24..32], 1u64.to_be_bytes());
+    }
+
+    fn credit_one_lot(recipient: Address) -> i32 {
+        // 1 lot
+        set_msg_value(hex!(
+            "00000000000000000000000000000000000000000000000000000000000F4240"
+        ));
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_0_CREDIT_ETH];
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&recipient);
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    pub fn test_deposit_above_u64_balance() {
+        let recipient = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        crate::registry::set_operator_sender(&recipient);
+        let key = &TraderTokenKey {
+            trader: recipient,
+            token: NATIVE_TOKEN,
+        };
+        set_free_balance(&recipient, &NATIVE_TOKEN, LotBalance(u64::MAX as u128));
+
+        assert_eq!(credit_one_lot(recipient), 0);
+
+        let trader_token_state_bytes = read_trader_token_state(key);
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, 0);
+        assert_eq!(trader_token_state.lots_free_high, 1);
+        assert_eq!(
+            trader_token_state.free_balance(),
+            LotBalance(u64::MAX as u128 + 1)
+        );
+    }
+
+    #[test]
+    pub fn test_deposit_overflow() {
+        let recipient = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        crate::registry::set_operator_sender(&recipient);
+        set_free_balance(&recipient, &NATIVE_TOKEN, LotBalance(u128::MAX));
+
+        assert_eq!(credit_one_lot(recipient), 1);
+    }
+
+    #[test]
+    pub fn test_deposit_above_u64_lots() {
+        let recipient = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        crate::registry::set_operator_sender(&recipient);
+
+        // 2^128 wei is more than u64::MAX lots. It used to wrap to a smaller credit.
+        set_msg_value(hex!(
+            "0000000000000000000000000000000100000000000000000000000000000000"
+        ));
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_0_CREDIT_ETH];
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&recipient);
+        set_test_args(test_args.clone());
+
+        assert_eq!(user_entrypoint(test_args.len()), 1);
     }
 }
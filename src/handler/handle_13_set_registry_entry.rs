@@ -0,0 +1,127 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    msg_sender,
+    registry::NUM_LISTS,
+    state::{RegistryEntry, RegistryKey, SlotState},
+    types::Address,
+    OWNER,
+};
+
+pub const HANDLE_13_SET_REGISTRY_ENTRY: u8 = 13;
+pub const HANDLE_13_PAYLOAD_LEN: usize = core::mem::size_of::<RegistryEntryParams>();
+
+#[repr(C)]
+struct RegistryEntryParams {
+    /// The account to add or remove
+    pub account: Address,
+
+    /// The list to update, see [`crate::registry`]
+    pub list: u8,
+
+    /// 1 to add `account` to `list`, 0 to remove it
+    pub registered: u8,
+}
+
+/// Add an account to or remove it from a registry list
+///
+/// * Only callable by [`OWNER`].
+///
+/// * Fails if `list` is unknown.
+///
+pub fn handle_13_set_registry_entry(payload: &[u8]) -> i32 {
+    let params = unsafe { &*(payload.as_ptr() as *const RegistryEntryParams) };
+
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    if *sender != OWNER || params.list >= NUM_LISTS {
+        return 1;
+    }
+
+    let key = &RegistryKey {
+        account: params.account,
+        list: params.list,
+    };
+
+    let mut entry_maybe = MaybeUninit::<RegistryEntry>::uninit();
+    let entry = unsafe { RegistryEntry::load(key, &mut entry_maybe) };
+    entry.registered = params.registered;
+
+    unsafe {
+        entry.store(key);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{
+        hostio::*,
+        registry::{is_registered, OPERATOR},
+        test_fixtures::set_sender,
+        user_entrypoint,
+    };
+
+    const MARKET: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+
+    fn set_registry_entry(sender: &Address, params: &RegistryEntryParams) -> i32 {
+        set_sender(sender);
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_13_SET_REGISTRY_ENTRY];
+        test_args.extend_from_slice(&(HANDLE_13_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(unsafe {
+            core::slice::from_raw_parts(
+                params as *const RegistryEntryParams as *const u8,
+                core::mem::size_of::<RegistryEntryParams>(),
+            )
+        });
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    fn test_set_registry_entry() {
+        let mut params = RegistryEntryParams {
+            account: MARKET,
+            list: OPERATOR,
+            registered: 1,
+        };
+        assert_eq!(set_registry_entry(&OWNER, &params), 0);
+        assert!(is_registered(&MARKET, OPERATOR));
+
+        params.registered = 0;
+        assert_eq!(set_registry_entry(&OWNER, &params), 0);
+        assert!(!is_registered(&MARKET, OPERATOR));
+    }
+
+    #[test]
+    fn test_set_registry_entry_rejects_non_owner() {
+        let params = RegistryEntryParams {
+            account: MARKET,
+            list: OPERATOR,
+            registered: 1,
+        };
+        assert_eq!(set_registry_entry(&MARKET, &params), 1);
+        assert!(!is_registered(&MARKET, OPERATOR));
+    }
+
+    #[test]
+    fn test_set_registry_entry_rejects_unknown_list() {
+        let params = RegistryEntryParams {
+            account: MARKET,
+            list: NUM_LISTS,
+            registered: 1,
+        };
+        assert_eq!(set_registry_entry(&OWNER, &params), 1);
+    }
+}
@@ -1,13 +1,14 @@
 use core::mem::MaybeUninit;
 
 use crate::{
-    erc20::transfer_from,
-    msg_sender,
+    erc20::{transfer_from, transfer_from_measured},
+    events::emit_deposit,
+    guard, msg_sender,
     quantities::{Atoms, Lots},
+    registry::{self, FEE_ON_TRANSFER_TOKEN},
     state::{SlotState, TraderTokenKey, TraderTokenState},
-    storage_flush_cache,
     types::Address,
-    ADDRESS,
+    ADDRESS, FLUSH_POLICY,
 };
 
 pub const HANDLE_1_CREDIT_ERC20: u8 = 1;
@@ -32,44 +33,89 @@ struct CreditERC20Params {
 }
 
 /// Credit an ERC20 token to a recipient
+///
+/// * For tokens in the [`FEE_ON_TRANSFER_TOKEN`] registry list the contract's token
+///   balance is measured before and after the transfer and only the received lots are
+///   credited, capped at `lots`. Received dust smaller than a lot is not credited.
+///
+/// * Fails if `recipient` only accepts credits from operators and `msg_sender` is not
+///   one, see [`crate::state::TraderConfig`].
+///
+/// * Fails if the free balance of `recipient` would overflow.
+///
+/// * Writes of earlier calls in the batch are flushed before calling the token so that a
+///   token re-entering the contract sees them.
+///
+/// * Emits a `Deposit` log for the credited lots.
+///
 pub fn handle_1_credit_erc20(payload: &[u8]) -> i32 {
+    if guard::check_operator() != 0 {
+        return 1;
+    }
+
     let params = unsafe { &*(payload.as_ptr() as *const CreditERC20Params) };
 
+    credit_erc20_lots(&params.token, &params.recipient, params.lots)
+}
+
+/// Pull `lots` of `token` from `msg_sender` and credit them to `recipient`
+pub(crate) fn credit_erc20_lots(token: &Address, recipient: &Address, lots: Lots) -> i32 {
+    if guard::check_recipient_operator(recipient) != 0 {
+        return 1;
+    }
+
     let mut sender_maybe = MaybeUninit::<Address>::uninit();
     let sender = unsafe {
         msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
         sender_maybe.assume_init_ref()
     };
 
-    let atoms = Atoms::from(&params.lots);
+    let atoms = Atoms::from(&lots);
 
-    // Transfer tokens to smart contract, not params.recipient
-    let result = transfer_from(&params.token, sender, &ADDRESS, &atoms);
+    // Transfer tokens to smart contract, not recipient
+    let lots = if registry::is_registered(token, FEE_ON_TRANSFER_TOKEN) {
+        FLUSH_POLICY.before_external_call();
+        match transfer_from_measured(token, sender, &ADDRESS, &atoms) {
+            // Received atoms beyond u64 lots exceed `lots` as well
+            Some(received_atoms) => match received_atoms.split_lots() {
+                Some((received_lots, _dust)) if received_lots.0 < lots.0 => received_lots,
+                _ => lots,
+            },
+            None => return 1,
+        }
+    } else {
+        FLUSH_POLICY.before_external_call();
+        let result = transfer_from(token, sender, &ADDRESS, &atoms);
 
-    // unsafe {
-    //     let msg = b"Call result";
-    //     log_txt(msg.as_ptr(), msg.len());
-    //     log_i64(result as i64);
-    // }
+        // unsafe {
+        //     let msg = b"Call result";
+        //     log_txt(msg.as_ptr(), msg.len());
+        //     log_i64(result as i64);
+        // }
 
-    if result != 0 {
-        return 1;
-    }
+        if result != 0 {
+            return 1;
+        }
+        lots
+    };
 
     // Credit lots
     let key = &TraderTokenKey {
-        trader: params.recipient,
-        token: params.token,
+        trader: *recipient,
+        token: *token,
     };
 
     let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
     let trader_token_state = unsafe { TraderTokenState::load(key, &mut trader_token_state_maybe) };
-    trader_token_state.lots_free += params.lots;
+    let Some(free_balance) = trader_token_state.free_balance().checked_add_lots(lots) else {
+        return 1;
+    };
+    trader_token_state.set_free_balance(free_balance);
 
     unsafe {
         trader_token_state.store(key);
-        storage_flush_cache(true);
     }
+    emit_deposit(recipient, token, lots);
 
     0
 }
@@ -82,9 +128,12 @@ mod test {
 
     use crate::{
         getter::read_trader_token_state,
+        handler::{HANDLE_0_CREDIT_ETH, HANDLE_0_PAYLOAD_LEN},
         hostio::*,
         quantities::Lots,
-        state::{TraderTokenKey, TraderTokenState},
+        state::{SlotKey, TraderTokenKey, TraderTokenState},
+        test_fixtures::{encode_u256, set_token_calls_succeed},
+        types::NATIVE_TOKEN,
         user_entrypoint,
     };
 
@@ -93,19 +142,16 @@ mod test {
     #[test]
     pub fn test_deposit_erc20() {
         // Set hostios
-        let mut msg_sender = [0u8; 32];
-        msg_sender[12..].copy_from_slice(&hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E"));
-        set_msg_sender(msg_sender);
+        crate::registry::set_operator_sender(&hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E"));
 
-        let mut return_data = vec![0u8; 32];
-        return_data[31] = 1;
-        set_return_data(return_data);
+        set_token_calls_succeed();
 
         // Set args
         let mut test_args: Vec<u8> = vec![];
         let num_calls: u8 = 1;
         test_args.push(num_calls);
         test_args.push(HANDLE_1_CREDIT_ERC20);
+        test_args.extend_from_slice(&(HANDLE_1_PAYLOAD_LEN as u16).to_le_bytes());
 
         let payload = CreditERC20Params {
             token: hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a"),
@@ -146,4 +192,88 @@ mod test {
         assert_eq!(trader_token_state.lots_free.0, 1);
         assert_eq!(trader_token_state.lots_locked.0, 0);
     }
+
+    #[test]
+    pub fn test_deposit_fee_on_transfer_erc20() {
+        crate::registry::set_operator_sender(&hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E"));
+        crate::registry::register(
+            &hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a"),
+            FEE_ON_TRANSFER_TOKEN,
+        );
+
+        // Balance before, transferFrom() result and balance after. 2 lots are sent
+        // but 1.5 lots arrive.
+        push_return_data(encode_u256(0));
+        push_return_data(encode_u256(1));
+        push_return_data(encode_u256(1_500_000));
+
+        let payload = CreditERC20Params {
+            token: hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a"),
+            recipient: hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E"),
+            lots: Lots(2),
+        };
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_1_CREDIT_ERC20];
+        test_args.extend_from_slice(&(HANDLE_1_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(unsafe {
+            core::slice::from_raw_parts(
+                &payload as *const CreditERC20Params as *const u8,
+                core::mem::size_of::<CreditERC20Params>(),
+            )
+        });
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 0);
+
+        let key = &TraderTokenKey {
+            trader: payload.recipient,
+            token: payload.token,
+        };
+        let trader_token_state_bytes = read_trader_token_state(key);
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, 1);
+    }
+
+    #[test]
+    pub fn test_flush_before_transfer_from() {
+        let trader = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        crate::registry::set_operator_sender(&trader);
+
+        // 1 lot of ETH, then an ERC20 whose transferFrom() returns false
+        set_msg_value(hex!(
+            "00000000000000000000000000000000000000000000000000000000000F4240"
+        ));
+        set_return_data(encode_u256(0));
+
+        let payload = CreditERC20Params {
+            token: hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a"),
+            recipient: trader,
+            lots: Lots(1),
+        };
+
+        let mut test_args: Vec<u8> = vec![2, HANDLE_0_CREDIT_ETH];
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&trader);
+        test_args.push(HANDLE_1_CREDIT_ERC20);
+        test_args.extend_from_slice(&(HANDLE_1_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(unsafe {
+            core::slice::from_raw_parts(
+                &payload as *const CreditERC20Params as *const u8,
+                core::mem::size_of::<CreditERC20Params>(),
+            )
+        });
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 1);
+
+        // The batch fails after the token call, so only the flush before the call could
+        // have written the ETH credit
+        let eth_key = &TraderTokenKey {
+            trader,
+            token: NATIVE_TOKEN,
+        };
+        let flushed = get_flushed_storage_value(&eth_key.to_keccak256()).unwrap();
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(flushed.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free, Lots(1));
+    }
 }
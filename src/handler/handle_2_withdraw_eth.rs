@@ -0,0 +1,253 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    eth,
+    events::emit_withdraw,
+    msg_sender,
+    quantities::{Atoms, Lots},
+    settlement_hook::on_goblin_settle,
+    state::{SlotState, TraderConfig, TraderConfigKey, TraderTokenKey, TraderTokenState},
+    types::{Address, NATIVE_TOKEN},
+    withdrawal::resolve_withdraw_lots,
+    FLUSH_POLICY,
+};
+
+pub const HANDLE_2_WITHDRAW_ETH: u8 = 2;
+pub const HANDLE_2_PAYLOAD_LEN: usize = core::mem::size_of::<WithdrawETHParams>();
+
+#[repr(C)]
+struct WithdrawETHParams {
+    /// Wei is sent to this address. This allows a wallet to withdraw to another wallet.
+    ///
+    /// Pass the zero address to use the default recipient of `msg_sender`'s trader config.
+    pub recipient: Address,
+
+    /// The lots to withdraw from the free balance of `msg_sender`. See [`crate::withdrawal`]
+    /// for the withdraw all and all minus encodings.
+    ///
+    /// The lots bytes should be encoded in **little endian** for zero copy deserialization.
+    pub lots: Lots,
+}
+
+/// Withdraw free ETH lots of `msg_sender` to a recipient
+///
+/// * Fails if `msg_sender` has fewer free lots than requested.
+///
+/// * Zero lots, e.g. withdrawing all of an empty balance, succeed without a transfer,
+///   settlement hook call or log.
+///
+/// * The trader state is written and flushed before ETH is sent out so that a recipient
+///   re-entering the contract sees the reduced balance.
+///
+/// * If the recipient is the settlement hook of `msg_sender`, it is called with
+///   `onGoblinSettle` after the transfer. See [`crate::settlement_hook`].
+///
+/// * Emits a `Withdraw` log once the transfer succeeds.
+///
+pub fn handle_2_withdraw_eth(payload: &[u8]) -> i32 {
+    let params = unsafe { &*(payload.as_ptr() as *const WithdrawETHParams) };
+
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    let key = &TraderTokenKey {
+        trader: *sender,
+        token: NATIVE_TOKEN,
+    };
+
+    let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+    let trader_token_state = unsafe { TraderTokenState::load(key, &mut trader_token_state_maybe) };
+
+    let free_balance = trader_token_state.free_balance();
+    let Some(lots) = resolve_withdraw_lots(free_balance, params.lots) else {
+        return 1;
+    };
+    // Nothing to send, e.g. when withdrawing all of an empty balance
+    if lots.0 == 0 {
+        return 0;
+    }
+    let Some(free_balance) = free_balance.checked_sub_lots(lots) else {
+        return 1;
+    };
+    trader_token_state.set_free_balance(free_balance);
+
+    unsafe {
+        trader_token_state.store(key);
+    }
+    FLUSH_POLICY.before_external_call();
+
+    let mut trader_config_maybe = MaybeUninit::<TraderConfig>::uninit();
+    let trader_config = unsafe {
+        TraderConfig::load(
+            &TraderConfigKey { trader: *sender },
+            &mut trader_config_maybe,
+        )
+    };
+    let recipient = trader_config.resolve_recipient(sender, &params.recipient);
+
+    let atoms = Atoms::from(&lots);
+    if eth::transfer(&recipient, &atoms) != 0 {
+        return 1;
+    }
+    if trader_config.is_settlement_hook(&recipient)
+        && on_goblin_settle(&recipient, sender, &NATIVE_TOKEN, &atoms) != 0
+    {
+        return 1;
+    }
+    emit_withdraw(sender, &NATIVE_TOKEN, &recipient, lots);
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    use crate::{
+        get_call_count, get_logs,
+        getter::read_trader_token_state,
+        handler::{HANDLE_0_CREDIT_ETH, HANDLE_0_PAYLOAD_LEN},
+        quantities::LotBalance,
+        set_msg_value, set_test_args,
+        test_fixtures::{read_free_balance, set_sender},
+        user_entrypoint,
+    };
+
+    fn credit_eth(trader: &Address) {
+        crate::registry::set_operator_sender(trader);
+
+        // Set msg.value to 2 * 10^6 in big endian
+        let msg_value = hex!("00000000000000000000000000000000000000000000000000000000001E8480");
+        set_msg_value(msg_value);
+
+        let mut test_args: Vec<u8> = vec![];
+        test_args.push(1);
+        test_args.push(HANDLE_0_CREDIT_ETH);
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(trader);
+        set_test_args(test_args.clone());
+
+        assert_eq!(user_entrypoint(test_args.len()), 0);
+        set_msg_value([0u8; 32]);
+    }
+
+    fn withdraw_eth(params: &WithdrawETHParams) -> i32 {
+        let mut test_args: Vec<u8> = vec![];
+        test_args.push(1);
+        test_args.push(HANDLE_2_WITHDRAW_ETH);
+        test_args.extend_from_slice(&(HANDLE_2_PAYLOAD_LEN as u16).to_le_bytes());
+
+        let payload_bytes: &[u8] = unsafe {
+            core::slice::from_raw_parts(
+                params as *const WithdrawETHParams as *const u8,
+                core::mem::size_of::<WithdrawETHParams>(),
+            )
+        };
+        test_args.extend_from_slice(payload_bytes);
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    pub fn test_withdraw_eth() {
+        let trader = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        set_sender(&trader);
+
+        credit_eth(&trader);
+
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1"),
+            lots: Lots(1),
+        });
+        assert_eq!(result, 0);
+
+        let key = &TraderTokenKey {
+            trader,
+            token: NATIVE_TOKEN,
+        };
+        let trader_token_state_bytes = read_trader_token_state(key);
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+
+        assert_eq!(trader_token_state.lots_free.0, 1);
+        assert_eq!(trader_token_state.lots_locked.0, 0);
+    }
+
+    #[test]
+    pub fn test_withdraw_all_eth() {
+        let trader = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        credit_eth(&trader);
+
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: trader,
+            lots: crate::withdrawal::WITHDRAW_ALL,
+        });
+        assert_eq!(result, 0);
+        assert_eq!(read_free_balance(&trader, &NATIVE_TOKEN), LotBalance(0));
+        let call_count = get_call_count();
+        let log_count = get_logs().len();
+
+        // Withdrawing all of an empty balance succeeds with zero lots
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: trader,
+            lots: crate::withdrawal::WITHDRAW_ALL,
+        });
+        assert_eq!(result, 0);
+        assert_eq!(get_call_count(), call_count);
+        assert_eq!(get_logs().len(), log_count);
+    }
+
+    #[test]
+    pub fn test_withdraw_all_of_empty_eth_balance() {
+        let trader = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        set_sender(&trader);
+
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: trader,
+            lots: crate::withdrawal::WITHDRAW_ALL,
+        });
+        assert_eq!(result, 0);
+        assert_eq!(get_call_count(), 0);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    pub fn test_withdraw_all_minus_eth() {
+        let trader = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        credit_eth(&trader);
+
+        // Keep 1 of 2 lots
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: trader,
+            lots: Lots(crate::withdrawal::ALL_MINUS_FLAG | 1),
+        });
+        assert_eq!(result, 0);
+        assert_eq!(read_free_balance(&trader, &NATIVE_TOKEN), LotBalance(1));
+
+        // The buffer exceeds the balance
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: trader,
+            lots: Lots(crate::withdrawal::ALL_MINUS_FLAG | 2),
+        });
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    pub fn test_withdraw_eth_insufficient_funds() {
+        let trader = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+        set_sender(&trader);
+
+        credit_eth(&trader);
+
+        let result = withdraw_eth(&WithdrawETHParams {
+            recipient: trader,
+            lots: Lots(3),
+        });
+        assert_eq!(result, 1);
+    }
+}
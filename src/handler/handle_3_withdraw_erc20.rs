@@ -0,0 +1,296 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    erc20::transfer,
+    events::emit_withdraw,
+    msg_sender,
+    quantities::{Atoms, Lots},
+    settlement_hook::on_goblin_settle,
+    state::{SlotState, TraderConfig, TraderConfigKey, TraderTokenKey, TraderTokenState},
+    types::Address,
+    withdrawal::resolve_withdraw_lots,
+    FLUSH_POLICY,
+};
+
+pub const HANDLE_3_WITHDRAW_ERC20: u8 = 3;
+pub const HANDLE_3_PAYLOAD_LEN: usize = core::mem::size_of::<WithdrawERC20Params>();
+
+#[repr(C)]
+struct WithdrawERC20Params {
+    /// The token to withdraw
+    pub token: Address,
+
+    /// Tokens are sent to this address. This allows a wallet to withdraw to another wallet.
+    ///
+    /// Pass the zero address to use the default recipient of `msg_sender`'s trader config.
+    pub recipient: Address,
+
+    /// The lots to withdraw from the free balance of `msg_sender`. See [`crate::withdrawal`]
+    /// for the withdraw all and all minus encodings.
+    ///
+    /// The lots bytes should be encoded in **little endian** for zero copy deserialization.
+    pub lots: Lots,
+}
+
+/// Withdraw free ERC20 lots of `msg_sender` to a recipient
+///
+/// * Fails if `msg_sender` has fewer free lots than requested.
+///
+/// * Zero lots, e.g. withdrawing all of an empty balance, succeed without a transfer,
+///   settlement hook call or log.
+///
+/// * The trader state is written and flushed before calling the token so that a malicious
+///   token re-entering the contract sees the reduced balance.
+///
+/// * If the recipient is the settlement hook of `msg_sender`, it is called with
+///   `onGoblinSettle` after the transfer. See [`crate::settlement_hook`].
+///
+/// * Emits a `Withdraw` log once the transfer succeeds.
+///
+pub fn handle_3_withdraw_erc20(payload: &[u8]) -> i32 {
+    let params = unsafe { &*(payload.as_ptr() as *const WithdrawERC20Params) };
+
+    withdraw_erc20_lots(&params.token, &params.recipient, params.lots)
+}
+
+/// Withdraw free `lots` of `token` from `msg_sender` to `recipient`. `lots` can use the
+/// encodings of [`crate::withdrawal`].
+pub(crate) fn withdraw_erc20_lots(token: &Address, recipient: &Address, lots: Lots) -> i32 {
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    let key = &TraderTokenKey {
+        trader: *sender,
+        token: *token,
+    };
+
+    let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+    let trader_token_state = unsafe { TraderTokenState::load(key, &mut trader_token_state_maybe) };
+
+    let free_balance = trader_token_state.free_balance();
+    let Some(lots) = resolve_withdraw_lots(free_balance, lots) else {
+        return 1;
+    };
+    // Nothing to send, e.g. when withdrawing all of an empty balance
+    if lots.0 == 0 {
+        return 0;
+    }
+    let Some(free_balance) = free_balance.checked_sub_lots(lots) else {
+        return 1;
+    };
+    trader_token_state.set_free_balance(free_balance);
+
+    unsafe {
+        trader_token_state.store(key);
+    }
+    FLUSH_POLICY.before_external_call();
+
+    let mut trader_config_maybe = MaybeUninit::<TraderConfig>::uninit();
+    let trader_config = unsafe {
+        TraderConfig::load(
+            &TraderConfigKey { trader: *sender },
+            &mut trader_config_maybe,
+        )
+    };
+    let recipient = trader_config.resolve_recipient(sender, recipient);
+
+    let atoms = Atoms::from(&lots);
+    if transfer(token, &recipient, &atoms) != 0 {
+        return 1;
+    }
+    if trader_config.is_settlement_hook(&recipient)
+        && on_goblin_settle(&recipient, sender, token, &atoms) != 0
+    {
+        return 1;
+    }
+    emit_withdraw(sender, token, &recipient, lots);
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{
+        getter::read_trader_token_state,
+        hostio::*,
+        test_fixtures::{read_free_balance, set_up_trader_with_lots},
+        user_entrypoint,
+    };
+
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    fn encode_params(params: &WithdrawERC20Params) -> Vec<u8> {
+        unsafe {
+            core::slice::from_raw_parts(
+                params as *const WithdrawERC20Params as *const u8,
+                core::mem::size_of::<WithdrawERC20Params>(),
+            )
+        }
+        .to_vec()
+    }
+
+    fn withdraw_erc20(params: &WithdrawERC20Params) -> i32 {
+        let mut test_args: Vec<u8> = vec![];
+        test_args.push(1);
+        test_args.push(HANDLE_3_WITHDRAW_ERC20);
+        test_args.extend_from_slice(&(HANDLE_3_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&encode_params(params));
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    pub fn test_withdraw_erc20() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1"),
+            lots: Lots(2),
+        });
+        assert_eq!(result, 0);
+
+        let key = &TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        };
+        let trader_token_state_bytes = read_trader_token_state(key);
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+
+        assert_eq!(trader_token_state.lots_free.0, 3);
+        assert_eq!(trader_token_state.lots_locked.0, 0);
+
+        // Check Withdraw log
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics[0], crate::events::WITHDRAW_TOPIC);
+        assert_eq!(topics[1][12..], TRADER);
+        assert_eq!(topics[2][12..], TOKEN);
+        assert_eq!(
+            data[12..32],
+            hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1")
+        );
+        assert_eq!(data[56..64], 2u64.to_be_bytes());
+    }
+
+    #[test]
+    pub fn test_withdraw_all_erc20() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: crate::withdrawal::WITHDRAW_ALL,
+        });
+        assert_eq!(result, 0);
+        assert_eq!(read_free_balance(&TRADER, &TOKEN).0, 0);
+
+        let (_, calldata) = get_last_call();
+        assert_eq!(calldata[60..68], 5_000_000u64.to_be_bytes());
+    }
+
+    #[test]
+    pub fn test_withdraw_all_of_empty_erc20_balance() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 0);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: crate::withdrawal::WITHDRAW_ALL,
+        });
+        assert_eq!(result, 0);
+        assert_eq!(get_call_count(), 0);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    pub fn test_withdraw_all_minus_erc20() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: Lots(crate::withdrawal::ALL_MINUS_FLAG | 2),
+        });
+        assert_eq!(result, 0);
+        assert_eq!(read_free_balance(&TRADER, &TOKEN).0, 2);
+
+        // The buffer exceeds the balance
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: Lots(crate::withdrawal::ALL_MINUS_FLAG | 3),
+        });
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    pub fn test_exact_withdrawal_with_top_bit_is_all_minus() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        // 2^63 lots can't be withdrawn exactly. The top bit makes it all minus 0.
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: Lots(1 << 63),
+        });
+        assert_eq!(result, 0);
+        assert_eq!(read_free_balance(&TRADER, &TOKEN).0, 0);
+
+        let (_, calldata) = get_last_call();
+        assert_eq!(calldata[60..68], 5_000_000u64.to_be_bytes());
+    }
+
+    #[test]
+    pub fn test_withdraw_erc20_to_zero_address_sends_to_trader() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 1);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: [0u8; 20],
+            lots: Lots(1),
+        });
+        assert_eq!(result, 0);
+
+        // transfer(address,uint256) with the left padded recipient
+        let (contract, calldata) = get_last_call();
+        assert_eq!(contract, TOKEN);
+        assert_eq!(calldata[16..36], TRADER);
+    }
+
+    #[test]
+    pub fn test_withdraw_erc20_insufficient_funds() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 1);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: Lots(2),
+        });
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    pub fn test_withdraw_erc20_transfer_returns_false() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 1);
+        set_return_data(vec![0u8; 32]);
+
+        let result = withdraw_erc20(&WithdrawERC20Params {
+            token: TOKEN,
+            recipient: TRADER,
+            lots: Lots(1),
+        });
+        assert_eq!(result, 1);
+    }
+}
@@ -0,0 +1,194 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    guard,
+    quantities::Lots,
+    state::{SlotState, TraderTokenKey, TraderTokenState},
+    types::Address,
+};
+
+pub const HANDLE_4_LOCK_FUNDS: u8 = 4;
+pub const HANDLE_4_PAYLOAD_LEN: usize = core::mem::size_of::<LockFundsParams>();
+
+#[repr(C)]
+struct LockFundsParams {
+    /// The trader whose funds are locked
+    pub trader: Address,
+
+    /// The token to lock
+    pub token: Address,
+
+    /// The lots to move from the free to the locked balance.
+    ///
+    /// The lots bytes should be encoded in **little endian** for zero copy deserialization.
+    pub lots: Lots,
+}
+
+/// Lock free lots of a trader, e.g. to collateralize a resting order on a market
+///
+/// * Only callable by markets in the [`crate::registry::MARKET`] list. A single deposit
+///   can back orders on every market trading the token.
+///
+/// * Fails if the trader has fewer free lots than requested.
+///
+pub fn handle_4_lock_funds(payload: &[u8]) -> i32 {
+    if guard::check_market() != 0 {
+        return 1;
+    }
+
+    let params = unsafe { &*(payload.as_ptr() as *const LockFundsParams) };
+
+    let key = &TraderTokenKey {
+        trader: params.trader,
+        token: params.token,
+    };
+
+    let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+    let trader_token_state = unsafe { TraderTokenState::load(key, &mut trader_token_state_maybe) };
+
+    let Some(free_balance) = trader_token_state
+        .free_balance()
+        .checked_sub_lots(params.lots)
+    else {
+        return 1;
+    };
+    let Some(locked_balance) = trader_token_state
+        .locked_balance()
+        .checked_add_lots(params.lots)
+    else {
+        return 1;
+    };
+    trader_token_state.set_free_balance(free_balance);
+    trader_token_state.set_locked_balance(locked_balance);
+
+    unsafe {
+        trader_token_state.store(key);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{
+        getter::read_trader_token_state,
+        hostio::*,
+        quantities::LotBalance,
+        test_fixtures::{set_free_balance, set_sender, set_up_trader_with_lots},
+        user_entrypoint,
+    };
+
+    const MARKET: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    fn lock_funds(sender: &Address, params: &LockFundsParams) -> i32 {
+        crate::registry::register(&MARKET, crate::registry::MARKET);
+
+        set_sender(sender);
+
+        let mut test_args: Vec<u8> = vec![];
+        test_args.push(1);
+        test_args.push(HANDLE_4_LOCK_FUNDS);
+        test_args.extend_from_slice(&(HANDLE_4_PAYLOAD_LEN as u16).to_le_bytes());
+
+        let payload_bytes: &[u8] = unsafe {
+            core::slice::from_raw_parts(
+                params as *const LockFundsParams as *const u8,
+                core::mem::size_of::<LockFundsParams>(),
+            )
+        };
+        test_args.extend_from_slice(payload_bytes);
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    pub fn test_lock_funds() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        let result = lock_funds(
+            &MARKET,
+            &LockFundsParams {
+                trader: TRADER,
+                token: TOKEN,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 0);
+
+        let key = &TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        };
+        let trader_token_state_bytes = read_trader_token_state(key);
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+
+        assert_eq!(trader_token_state.lots_free.0, 3);
+        assert_eq!(trader_token_state.lots_locked.0, 2);
+    }
+
+    #[test]
+    pub fn test_lock_funds_from_balance_above_u64() {
+        let key = &TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        };
+        set_free_balance(&TRADER, &TOKEN, LotBalance(1 << 64));
+
+        let result = lock_funds(
+            &MARKET,
+            &LockFundsParams {
+                trader: TRADER,
+                token: TOKEN,
+                lots: Lots(1),
+            },
+        );
+        assert_eq!(result, 0);
+
+        // 2^64 - 1 lots remain free
+        let trader_token_state_bytes = read_trader_token_state(key);
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, u64::MAX);
+        assert_eq!(trader_token_state.lots_free_high, 0);
+        assert_eq!(trader_token_state.lots_locked.0, 1);
+    }
+
+    #[test]
+    pub fn test_lock_funds_insufficient_funds() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 1);
+
+        let result = lock_funds(
+            &MARKET,
+            &LockFundsParams {
+                trader: TRADER,
+                token: TOKEN,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    pub fn test_lock_funds_rejects_non_market() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        // Traders can't lock funds, even their own
+        let result = lock_funds(
+            &TRADER,
+            &LockFundsParams {
+                trader: TRADER,
+                token: TOKEN,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 1);
+    }
+}
@@ -0,0 +1,205 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    guard,
+    quantities::Lots,
+    state::{SlotState, TraderTokenKey, TraderTokenState},
+    types::Address,
+};
+
+pub const HANDLE_6_SETTLE_FUNDS: u8 = 6;
+pub const HANDLE_6_PAYLOAD_LEN: usize = core::mem::size_of::<SettleFundsParams>();
+
+#[repr(C)]
+struct SettleFundsParams {
+    /// The token to settle
+    pub token: Address,
+
+    /// Locked lots are debited from this trader
+    pub from: Address,
+
+    /// Free lots are credited to this trader
+    pub to: Address,
+
+    /// The lots to settle.
+    ///
+    /// The lots bytes should be encoded in **little endian** for zero copy deserialization.
+    /// Since `lots` is 8 byte aligned, 4 padding bytes precede it.
+    pub lots: Lots,
+}
+
+/// Move locked lots of one trader to the free balance of another, e.g. when an order fills
+///
+/// * Only callable by markets in the [`crate::registry::MARKET`] list. A fill is settled
+///   with one call per token.
+///
+/// * Fails if `from` has fewer locked lots than requested.
+///
+pub fn handle_6_settle_funds(payload: &[u8]) -> i32 {
+    if guard::check_market() != 0 {
+        return 1;
+    }
+
+    let params = unsafe { &*(payload.as_ptr() as *const SettleFundsParams) };
+
+    let from_key = &TraderTokenKey {
+        trader: params.from,
+        token: params.token,
+    };
+
+    let mut from_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+    let from_state = unsafe { TraderTokenState::load(from_key, &mut from_state_maybe) };
+
+    let Some(locked_balance) = from_state.locked_balance().checked_sub_lots(params.lots) else {
+        return 1;
+    };
+    from_state.set_locked_balance(locked_balance);
+
+    // Store before loading `to` so that settling with oneself reads the debited state
+    unsafe {
+        from_state.store(from_key);
+    }
+
+    let to_key = &TraderTokenKey {
+        trader: params.to,
+        token: params.token,
+    };
+
+    let mut to_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+    let to_state = unsafe { TraderTokenState::load(to_key, &mut to_state_maybe) };
+    let Some(free_balance) = to_state.free_balance().checked_add_lots(params.lots) else {
+        return 1;
+    };
+    to_state.set_free_balance(free_balance);
+
+    unsafe {
+        to_state.store(to_key);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{
+        getter::read_trader_token_state,
+        hostio::*,
+        quantities::LotBalance,
+        test_fixtures::{set_locked_balance, set_sender},
+        user_entrypoint,
+    };
+
+    const MARKET: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+    const MAKER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+    const TAKER: Address = hex!("a6e41ffd769491a42a6e5ce453259b93983a22ef");
+
+    fn settle_funds(sender: &Address, params: &SettleFundsParams) -> i32 {
+        crate::registry::register(&MARKET, crate::registry::MARKET);
+
+        set_sender(sender);
+
+        let mut test_args: Vec<u8> = vec![];
+        test_args.push(1);
+        test_args.push(HANDLE_6_SETTLE_FUNDS);
+        test_args.extend_from_slice(&(HANDLE_6_PAYLOAD_LEN as u16).to_le_bytes());
+
+        let payload_bytes: &[u8] = unsafe {
+            core::slice::from_raw_parts(
+                params as *const SettleFundsParams as *const u8,
+                core::mem::size_of::<SettleFundsParams>(),
+            )
+        };
+        test_args.extend_from_slice(payload_bytes);
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    fn read_state(trader: Address) -> (u64, u64) {
+        let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+            trader,
+            token: TOKEN,
+        });
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+
+        (
+            trader_token_state.lots_locked.0,
+            trader_token_state.lots_free.0,
+        )
+    }
+
+    #[test]
+    pub fn test_settle_funds() {
+        set_locked_balance(&MAKER, &TOKEN, LotBalance(5));
+
+        let result = settle_funds(
+            &MARKET,
+            &SettleFundsParams {
+                token: TOKEN,
+                from: MAKER,
+                to: TAKER,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 0);
+
+        assert_eq!(read_state(MAKER), (3, 0));
+        assert_eq!(read_state(TAKER), (0, 2));
+    }
+
+    #[test]
+    pub fn test_settle_funds_with_self() {
+        set_locked_balance(&MAKER, &TOKEN, LotBalance(5));
+
+        let result = settle_funds(
+            &MARKET,
+            &SettleFundsParams {
+                token: TOKEN,
+                from: MAKER,
+                to: MAKER,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 0);
+
+        assert_eq!(read_state(MAKER), (3, 2));
+    }
+
+    #[test]
+    pub fn test_settle_funds_insufficient_locked_funds() {
+        set_locked_balance(&MAKER, &TOKEN, LotBalance(1));
+
+        let result = settle_funds(
+            &MARKET,
+            &SettleFundsParams {
+                token: TOKEN,
+                from: MAKER,
+                to: TAKER,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    pub fn test_settle_funds_rejects_non_market() {
+        set_locked_balance(&MAKER, &TOKEN, LotBalance(5));
+
+        let result = settle_funds(
+            &TAKER,
+            &SettleFundsParams {
+                token: TOKEN,
+                from: MAKER,
+                to: TAKER,
+                lots: Lots(2),
+            },
+        );
+        assert_eq!(result, 1);
+    }
+}
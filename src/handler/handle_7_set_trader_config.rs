@@ -0,0 +1,197 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    msg_sender,
+    state::{SlotState, TraderConfig, TraderConfigKey, DEFAULT_RECIPIENT},
+    types::Address,
+};
+
+pub const HANDLE_7_SET_TRADER_CONFIG: u8 = 7;
+pub const HANDLE_7_PAYLOAD_LEN: usize = core::mem::size_of::<TraderConfigParams>();
+
+#[repr(C)]
+struct TraderConfigParams {
+    /// Withdrawals to the zero address are sent here. Pass the zero address to unset,
+    /// so that such withdrawals go to `msg_sender`.
+    pub default_recipient: Address,
+
+    /// Set to 1 if `default_recipient` is a settlement hook contract that is notified of
+    /// withdrawals through `onGoblinSettle`. See [`crate::settlement_hook`].
+    pub settlement_hook: u8,
+
+    /// Set to 1 to only accept credits to `msg_sender` from accounts in the
+    /// [`crate::registry::OPERATOR`] list
+    pub operator_allowlist: u8,
+}
+
+/// Update the settings of `msg_sender`
+///
+/// * Fails if a settlement hook is requested without a default recipient.
+///
+pub fn handle_7_set_trader_config(payload: &[u8]) -> i32 {
+    let params = unsafe { &*(payload.as_ptr() as *const TraderConfigParams) };
+
+    if params.settlement_hook != 0 && params.default_recipient == DEFAULT_RECIPIENT {
+        return 1;
+    }
+
+    let mut sender_maybe = MaybeUninit::<Address>::uninit();
+    let sender = unsafe {
+        msg_sender(sender_maybe.as_mut_ptr() as *mut u8);
+        sender_maybe.assume_init_ref()
+    };
+
+    let key = &TraderConfigKey { trader: *sender };
+
+    let mut trader_config_maybe = MaybeUninit::<TraderConfig>::uninit();
+    let trader_config = unsafe { TraderConfig::load(key, &mut trader_config_maybe) };
+    trader_config.default_recipient = params.default_recipient;
+    trader_config.settlement_hook = params.settlement_hook;
+    trader_config.operator_allowlist = params.operator_allowlist;
+
+    unsafe {
+        trader_config.store(key);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{
+        hostio::*,
+        test_fixtures::{set_sender, set_token_calls_succeed},
+        user_entrypoint,
+    };
+
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+    const WALLET: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+
+    fn call(selector: u8, payload: &[u8]) -> i32 {
+        let mut test_args: Vec<u8> = vec![1, selector];
+        test_args.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        test_args.extend_from_slice(payload);
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    /// Credit 1 lot of ETH to TRADER, then withdraw it to the zero address
+    fn credit_and_withdraw_eth_to_default_recipient() {
+        crate::registry::set_operator_sender(&TRADER);
+
+        set_msg_value(hex!(
+            "00000000000000000000000000000000000000000000000000000000000F4240"
+        ));
+        assert_eq!(call(crate::handler::HANDLE_0_CREDIT_ETH, &TRADER), 0);
+        set_msg_value([0u8; 32]);
+
+        // Recipient, 4 padding bytes, then lots
+        let mut withdraw_payload = [0u8; 32];
+        withdraw_payload[24..].copy_from_slice(&1u64.to_le_bytes());
+        assert_eq!(
+            call(crate::handler::HANDLE_2_WITHDRAW_ETH, &withdraw_payload),
+            0
+        );
+    }
+
+    #[test]
+    fn test_withdraw_to_default_recipient() {
+        set_sender(&TRADER);
+
+        // Without a config, withdrawals to the zero address go to the trader
+        credit_and_withdraw_eth_to_default_recipient();
+        assert_eq!(get_last_call().0, TRADER);
+
+        let mut config_payload = [0u8; HANDLE_7_PAYLOAD_LEN];
+        config_payload[..20].copy_from_slice(&WALLET);
+        assert_eq!(call(HANDLE_7_SET_TRADER_CONFIG, &config_payload), 0);
+        credit_and_withdraw_eth_to_default_recipient();
+        assert_eq!(get_last_call().0, WALLET);
+
+        // Unset
+        assert_eq!(
+            call(HANDLE_7_SET_TRADER_CONFIG, &[0u8; HANDLE_7_PAYLOAD_LEN]),
+            0
+        );
+        credit_and_withdraw_eth_to_default_recipient();
+        assert_eq!(get_last_call().0, TRADER);
+    }
+
+    #[test]
+    fn test_withdraw_to_settlement_hook() {
+        set_sender(&TRADER);
+
+        let mut config_payload = [0u8; HANDLE_7_PAYLOAD_LEN];
+        config_payload[..20].copy_from_slice(&WALLET);
+        config_payload[20] = 1;
+        assert_eq!(call(HANDLE_7_SET_TRADER_CONFIG, &config_payload), 0);
+
+        let mut return_data = vec![0u8; 32];
+        return_data[..4].copy_from_slice(&crate::settlement_hook::ON_GOBLIN_SETTLE_SELECTOR);
+        set_return_data(return_data);
+
+        credit_and_withdraw_eth_to_default_recipient();
+        let (contract, calldata) = get_last_call();
+        assert_eq!(contract, WALLET);
+        assert_eq!(
+            calldata[..4],
+            crate::settlement_hook::ON_GOBLIN_SETTLE_SELECTOR
+        );
+        assert_eq!(calldata[144..164], TRADER);
+
+        // A hook needs a default recipient
+        config_payload[..20].copy_from_slice(&DEFAULT_RECIPIENT);
+        assert_eq!(call(HANDLE_7_SET_TRADER_CONFIG, &config_payload), 1);
+    }
+
+    /// Let TRADER only accept credits from operators
+    fn enable_operator_allowlist() {
+        set_sender(&TRADER);
+
+        let mut config_payload = [0u8; HANDLE_7_PAYLOAD_LEN];
+        config_payload[21] = 1;
+        assert_eq!(call(HANDLE_7_SET_TRADER_CONFIG, &config_payload), 0);
+    }
+
+    fn credit_erc20_to_trader() -> i32 {
+        let mut credit_payload = [0u8; crate::handler::HANDLE_1_PAYLOAD_LEN];
+        credit_payload[..20].copy_from_slice(&TOKEN);
+        credit_payload[20..40].copy_from_slice(&TRADER);
+        credit_payload[40..].copy_from_slice(&1u64.to_le_bytes());
+        call(crate::handler::HANDLE_1_CREDIT_ERC20, &credit_payload)
+    }
+
+    #[test]
+    fn test_operator_allowlist_eth() {
+        enable_operator_allowlist();
+        set_msg_value(hex!(
+            "00000000000000000000000000000000000000000000000000000000000F4240"
+        ));
+
+        crate::registry::set_operator_sender(&WALLET);
+        assert_eq!(call(crate::handler::HANDLE_0_CREDIT_ETH, &TRADER), 0);
+
+        // TRADER is not an operator, not even for credits to itself
+        set_sender(&TRADER);
+        assert_eq!(call(crate::handler::HANDLE_0_CREDIT_ETH, &TRADER), 1);
+    }
+
+    #[test]
+    fn test_operator_allowlist_erc20() {
+        enable_operator_allowlist();
+        set_token_calls_succeed();
+
+        crate::registry::set_operator_sender(&WALLET);
+        assert_eq!(credit_erc20_to_trader(), 0);
+
+        set_sender(&TRADER);
+        assert_eq!(credit_erc20_to_trader(), 1);
+        assert_eq!(get_call_count(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{guard, handler::credit_erc20_lots, quantities::Atoms, types::Address, write_result};
+
+pub const HANDLE_8_CREDIT_ERC20_ATOMS: u8 = 8;
+pub const HANDLE_8_PAYLOAD_LEN: usize = core::mem::size_of::<CreditERC20AtomsParams>();
+
+#[repr(C)]
+struct CreditERC20AtomsParams {
+    /// The token to credit
+    pub token: Address,
+
+    /// Credit input lots to `recipient`. This allows a wallet to fund another wallet
+    pub recipient: Address,
+
+    /// The raw token amount, encoded as a **big endian** uint256 like in ABI calls.
+    pub atoms: Atoms,
+}
+
+/// Credit an ERC20 token to a recipient, with the amount given in atoms
+///
+/// * Atoms are rounded down to whole lots. Only the atoms of these lots are pulled from
+///   `msg_sender`, so the dust stays in the wallet.
+///
+/// * Returns the dust as a big endian uint256.
+///
+/// * Fails if the amount is too large to fit in lots.
+///
+pub fn handle_8_credit_erc20_atoms(payload: &[u8]) -> i32 {
+    if guard::check_operator() != 0 {
+        return 1;
+    }
+
+    let params = unsafe { &*(payload.as_ptr() as *const CreditERC20AtomsParams) };
+
+    let Some((lots, dust)) = params.atoms.split_lots() else {
+        return 1;
+    };
+
+    let result = credit_erc20_lots(&params.token, &params.recipient, lots);
+    if result != 0 {
+        return result;
+    }
+
+    unsafe {
+        write_result(dust.to_be_bytes().as_ptr(), 32);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{
+        getter::read_trader_token_state,
+        hostio::*,
+        state::{TraderTokenKey, TraderTokenState},
+        test_fixtures::{encode_u256, set_token_calls_succeed},
+        user_entrypoint,
+    };
+
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    fn credit_erc20_atoms(params: &CreditERC20AtomsParams) -> i32 {
+        crate::registry::set_operator_sender(&TRADER);
+
+        // transferFrom() returns true
+        set_token_calls_succeed();
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_8_CREDIT_ERC20_ATOMS];
+        test_args.extend_from_slice(&(HANDLE_8_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(unsafe {
+            core::slice::from_raw_parts(
+                params as *const CreditERC20AtomsParams as *const u8,
+                core::mem::size_of::<CreditERC20AtomsParams>(),
+            )
+        });
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    fn test_credit_erc20_atoms() {
+        let result = credit_erc20_atoms(&CreditERC20AtomsParams {
+            token: TOKEN,
+            recipient: TRADER,
+            atoms: Atoms([0, 0, 0, 2_500_000u64.swap_bytes()]),
+        });
+        assert_eq!(result, 0);
+
+        // Dust is returned
+        let mut expected_dust = [0u8; 32];
+        expected_dust[24..].copy_from_slice(&500_000u64.to_be_bytes());
+        assert_eq!(get_test_result(), expected_dust);
+
+        // Only whole lots are pulled, i.e. 2 * 10^6 atoms
+        let (_, calldata) = get_last_call();
+        assert_eq!(calldata[92..100], 2_000_000u64.to_be_bytes());
+
+        let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        });
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, 2);
+    }
+
+    #[test]
+    fn test_credit_erc20_atoms_above_u64() {
+        // 2^64 + 999_999 atoms, i.e. about 18.4 tokens with 18 decimals
+        let result = credit_erc20_atoms(&CreditERC20AtomsParams {
+            token: TOKEN,
+            recipient: TRADER,
+            atoms: Atoms([0, 0, 1u64.swap_bytes(), 999_999u64.swap_bytes()]),
+        });
+        assert_eq!(result, 0);
+
+        let mut expected_dust = [0u8; 32];
+        expected_dust[24..].copy_from_slice(&551_615u64.to_be_bytes());
+        assert_eq!(get_test_result(), expected_dust);
+
+        // 18446744073710 lots = 2^64 + 448_384 atoms are pulled
+        let (_, calldata) = get_last_call();
+        assert_eq!(calldata[84..92], 1u64.to_be_bytes());
+        assert_eq!(calldata[92..100], 448_384u64.to_be_bytes());
+
+        let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        });
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, 18446744073710);
+    }
+
+    #[test]
+    fn test_credit_fee_on_transfer_erc20_atoms() {
+        crate::registry::register(&TOKEN, crate::registry::FEE_ON_TRANSFER_TOKEN);
+
+        // Balance before, transferFrom() result and balance after. 2 lots are pulled
+        // but 1.5 lots arrive.
+        push_return_data(encode_u256(0));
+        push_return_data(encode_u256(1));
+        push_return_data(encode_u256(1_500_000));
+
+        let result = credit_erc20_atoms(&CreditERC20AtomsParams {
+            token: TOKEN,
+            recipient: TRADER,
+            atoms: Atoms([0, 0, 0, 2_500_000u64.swap_bytes()]),
+        });
+        assert_eq!(result, 0);
+
+        let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+            trader: TRADER,
+            token: TOKEN,
+        });
+        let trader_token_state: &TraderTokenState =
+            unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+        assert_eq!(trader_token_state.lots_free.0, 1);
+    }
+
+    #[test]
+    fn test_credit_erc20_atoms_rejects_overflow() {
+        let result = credit_erc20_atoms(&CreditERC20AtomsParams {
+            token: TOKEN,
+            recipient: TRADER,
+            atoms: Atoms([1u64.swap_bytes(), 0, 0, 0]),
+        });
+        assert_eq!(result, 1);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    handler::withdraw_erc20_lots, quantities::Atoms, types::Address, withdrawal::is_exact,
+    write_result,
+};
+
+pub const HANDLE_9_WITHDRAW_ERC20_ATOMS: u8 = 9;
+pub const HANDLE_9_PAYLOAD_LEN: usize = core::mem::size_of::<WithdrawERC20AtomsParams>();
+
+#[repr(C)]
+struct WithdrawERC20AtomsParams {
+    /// The token to withdraw
+    pub token: Address,
+
+    /// Tokens are sent to this address. Pass the zero address to use the default
+    /// recipient of `msg_sender`'s trader config.
+    pub recipient: Address,
+
+    /// The raw token amount, encoded as a **big endian** uint256 like in ABI calls.
+    pub atoms: Atoms,
+}
+
+/// Withdraw free ERC20 balance of `msg_sender` to a recipient, with the amount given
+/// in atoms
+///
+/// * Atoms are rounded down to whole lots and only these lots are withdrawn.
+///
+/// * Returns the dust that was not withdrawn as a big endian uint256.
+///
+/// * Fails if `msg_sender` has fewer free lots than requested or if the amount is too
+///   large to fit in lots.
+///
+pub fn handle_9_withdraw_erc20_atoms(payload: &[u8]) -> i32 {
+    let params = unsafe { &*(payload.as_ptr() as *const WithdrawERC20AtomsParams) };
+
+    let Some((lots, dust)) = params.atoms.split_lots() else {
+        return 1;
+    };
+
+    // Large amounts would be read as withdraw all minus
+    if !is_exact(lots) {
+        return 1;
+    }
+
+    let result = withdraw_erc20_lots(&params.token, &params.recipient, lots);
+    if result != 0 {
+        return result;
+    }
+
+    unsafe {
+        write_result(dust.to_be_bytes().as_ptr(), 32);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use crate::{hostio::*, test_fixtures::set_up_trader_with_lots, user_entrypoint};
+
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    fn withdraw_erc20_atoms(atoms: u64) -> i32 {
+        let params = WithdrawERC20AtomsParams {
+            token: TOKEN,
+            recipient: TRADER,
+            atoms: Atoms([0, 0, 0, atoms.swap_bytes()]),
+        };
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_9_WITHDRAW_ERC20_ATOMS];
+        test_args.extend_from_slice(&(HANDLE_9_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(unsafe {
+            core::slice::from_raw_parts(
+                &params as *const WithdrawERC20AtomsParams as *const u8,
+                core::mem::size_of::<WithdrawERC20AtomsParams>(),
+            )
+        });
+        set_test_args(test_args.clone());
+
+        user_entrypoint(test_args.len())
+    }
+
+    #[test]
+    fn test_withdraw_erc20_atoms() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 5);
+
+        assert_eq!(withdraw_erc20_atoms(1_999_999), 0);
+
+        // 1 lot is withdrawn, 999_999 atoms of dust are returned
+        let mut expected_dust = [0u8; 32];
+        expected_dust[24..].copy_from_slice(&999_999u64.to_be_bytes());
+        assert_eq!(get_test_result(), expected_dust);
+
+        let (_, calldata) = get_last_call();
+        assert_eq!(calldata[60..68], 1_000_000u64.to_be_bytes());
+    }
+
+    #[test]
+    fn test_withdraw_erc20_atoms_insufficient_funds() {
+        set_up_trader_with_lots(&TRADER, &TOKEN, 1);
+
+        assert_eq!(withdraw_erc20_atoms(2_000_000), 1);
+    }
+}
@@ -1,5 +1,23 @@
 pub mod handle_0_credit_eth;
+pub mod handle_13_set_registry_entry;
 pub mod handle_1_credit_erc20;
+pub mod handle_2_withdraw_eth;
+pub mod handle_3_withdraw_erc20;
+pub mod handle_4_lock_funds;
+pub mod handle_5_unlock_funds;
+pub mod handle_6_settle_funds;
+pub mod handle_7_set_trader_config;
+pub mod handle_8_credit_erc20_atoms;
+pub mod handle_9_withdraw_erc20_atoms;
 
 pub use handle_0_credit_eth::*;
+pub use handle_13_set_registry_entry::*;
 pub use handle_1_credit_erc20::*;
+pub use handle_2_withdraw_eth::*;
+pub use handle_3_withdraw_erc20::*;
+pub use handle_4_lock_funds::*;
+pub use handle_5_unlock_funds::*;
+pub use handle_6_settle_funds::*;
+pub use handle_7_set_trader_config::*;
+pub use handle_8_credit_erc20_atoms::*;
+pub use handle_9_withdraw_erc20_atoms::*;
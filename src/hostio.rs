@@ -8,9 +8,12 @@ extern "C" {
     pub fn storage_load_bytes32(key: *const u8, dest: *mut u8);
     pub fn storage_cache_bytes32(key: *const u8, value: *const u8);
     pub fn storage_flush_cache(clear: bool);
+    pub fn transient_load_bytes32(key: *const u8, dest: *mut u8);
+    pub fn transient_store_bytes32(key: *const u8, value: *const u8);
     pub fn native_keccak256(bytes: *const u8, len: usize, output: *mut u8);
     pub fn msg_value(value: *mut u8);
     pub fn msg_sender(sender: *mut u8);
+    pub fn account_balance(address: *const u8, dest: *mut u8);
     pub fn call_contract(
         contract: *const u8,
         calldata: *const u8,
@@ -19,7 +22,15 @@ extern "C" {
         gas: u64,
         return_data_len: *mut usize,
     ) -> u8;
+    pub fn static_call_contract(
+        contract: *const u8,
+        calldata: *const u8,
+        calldata_len: usize,
+        gas: u64,
+        return_data_len: *mut usize,
+    ) -> u8;
     pub fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize;
+    pub fn emit_log(data: *const u8, len: usize, topics: usize);
 }
 
 // #[cfg(not(test))]
@@ -36,9 +47,12 @@ mod test_hooks {
     extern crate alloc;
     use alloc::vec::Vec;
     use core::cell::RefCell;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, VecDeque};
     use tiny_keccak::{Hasher, Keccak};
 
+    /// Topics and data of an emitted log
+    pub type Log = (Vec<[u8; 32]>, Vec<u8>);
+
     thread_local! {
         // Store the input args that will be read by read_args
         static TEST_ARGS: RefCell<Vec<u8>> = RefCell::new(Vec::new());
@@ -49,14 +63,36 @@ mod test_hooks {
         // Store key-value pairs for storage simulation
         static STORAGE: RefCell<HashMap<[u8; 32], [u8; 32]>> = RefCell::new(HashMap::new());
 
+        // Writes held by storage_cache_bytes32 until storage_flush_cache is called
+        static STORAGE_CACHE: RefCell<HashMap<[u8; 32], [u8; 32]>> = RefCell::new(HashMap::new());
+
+        // Transient storage, cleared at the end of the transaction
+        static TRANSIENT_STORAGE: RefCell<HashMap<[u8; 32], [u8; 32]>> = RefCell::new(HashMap::new());
+
         // Store the message value
         static MSG_VALUE: RefCell<[u8; 32]> = RefCell::new([0u8; 32]);
 
         // Add storage for sender address
         static MSG_SENDER: RefCell<[u8; 32]> = RefCell::new([0u8; 32]);
 
+        // Wei balance returned by account_balance for every address
+        static ACCOUNT_BALANCE: RefCell<[u8; 32]> = RefCell::new([0u8; 32]);
+
         // Simulate contract call return data
         static RETURN_DATA: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+
+        // Return data for upcoming calls. Each call pops one entry into RETURN_DATA,
+        // otherwise RETURN_DATA is reused.
+        static RETURN_DATA_QUEUE: RefCell<VecDeque<Vec<u8>>> = RefCell::new(VecDeque::new());
+
+        // Contract and calldata of the last call_contract
+        static LAST_CALL: RefCell<([u8; 20], Vec<u8>)> = RefCell::new(([0u8; 20], Vec::new()));
+
+        // Number of call_contract calls
+        static CALL_COUNT: RefCell<usize> = const { RefCell::new(0) };
+
+        // Topics and data of logs written by emit_log
+        static LOGS: RefCell<Vec<Log>> = RefCell::new(Vec::new());
     }
 
     pub fn set_test_args(args: Vec<u8>) {
@@ -69,7 +105,15 @@ mod test_hooks {
         TEST_RESULT.with(|test_result| test_result.borrow().clone())
     }
 
+    /// Read a slot as seen by the contract, i.e. cached writes take precedence
     pub fn get_storage_value(key: &[u8; 32]) -> Option<[u8; 32]> {
+        STORAGE_CACHE
+            .with(|cache| cache.borrow().get(key).cloned())
+            .or_else(|| get_flushed_storage_value(key))
+    }
+
+    /// Read a slot ignoring writes that were not flushed
+    pub fn get_flushed_storage_value(key: &[u8; 32]) -> Option<[u8; 32]> {
         STORAGE.with(|storage| storage.borrow().get(key).cloned())
     }
 
@@ -87,8 +131,16 @@ mod test_hooks {
         TEST_ARGS.with(|args| args.borrow_mut().clear());
         TEST_RESULT.with(|result| result.borrow_mut().clear());
         STORAGE.with(|storage| storage.borrow_mut().clear());
+        STORAGE_CACHE.with(|cache| cache.borrow_mut().clear());
+        TRANSIENT_STORAGE.with(|storage| storage.borrow_mut().clear());
         MSG_VALUE.with(|msg_value| *msg_value.borrow_mut() = [0u8; 32]);
         MSG_SENDER.with(|sender| *sender.borrow_mut() = [0u8; 32]);
+        ACCOUNT_BALANCE.with(|balance| *balance.borrow_mut() = [0u8; 32]);
+        RETURN_DATA.with(|return_data| return_data.borrow_mut().clear());
+        RETURN_DATA_QUEUE.with(|queue| queue.borrow_mut().clear());
+        LAST_CALL.with(|last_call| *last_call.borrow_mut() = ([0u8; 20], Vec::new()));
+        CALL_COUNT.with(|count| *count.borrow_mut() = 0);
+        LOGS.with(|logs| logs.borrow_mut().clear());
     }
 
     // Function to set the test sender address
@@ -98,12 +150,50 @@ mod test_hooks {
         });
     }
 
+    pub fn set_account_balance(balance: [u8; 32]) {
+        ACCOUNT_BALANCE.with(|account_balance| {
+            *account_balance.borrow_mut() = balance;
+        });
+    }
+
     pub fn set_return_data(data: Vec<u8>) {
         RETURN_DATA.with(|return_data| {
             *return_data.borrow_mut() = data;
         });
     }
 
+    /// Queue return data for the next contract call that hasn't been assigned one
+    pub fn push_return_data(data: Vec<u8>) {
+        RETURN_DATA_QUEUE.with(|queue| queue.borrow_mut().push_back(data));
+    }
+
+    /// Contract and calldata of the last `call_contract`
+    pub fn get_last_call() -> ([u8; 20], Vec<u8>) {
+        LAST_CALL.with(|last_call| last_call.borrow().clone())
+    }
+
+    /// Number of `call_contract` calls since the last `clear_state`
+    pub fn get_call_count() -> usize {
+        CALL_COUNT.with(|count| *count.borrow())
+    }
+
+    /// Topics and data of every log emitted since the last `clear_state`
+    pub fn get_logs() -> Vec<Log> {
+        LOGS.with(|logs| logs.borrow().clone())
+    }
+
+    /// Simulate a successful call by loading queued return data and returning its length
+    fn simulate_call(return_data_len: *mut usize) -> u8 {
+        if let Some(data) = RETURN_DATA_QUEUE.with(|queue| queue.borrow_mut().pop_front()) {
+            set_return_data(data);
+        }
+
+        RETURN_DATA.with(|return_data| unsafe {
+            *return_data_len = return_data.borrow().len();
+        });
+        0 // Indicate success
+    }
+
     #[no_mangle]
     pub unsafe extern "C" fn read_args(dest: *mut u8) {
         TEST_ARGS.with(|test_args| {
@@ -145,7 +235,7 @@ mod test_hooks {
 
     #[no_mangle]
     pub unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
-        STORAGE.with(|storage| {
+        STORAGE_CACHE.with(|storage| {
             let key_slice = core::slice::from_raw_parts(key, 32);
             let mut key_array = [0u8; 32];
             key_array.copy_from_slice(key_slice);
@@ -160,7 +250,39 @@ mod test_hooks {
 
     #[no_mangle]
     pub unsafe extern "C" fn storage_flush_cache(_clear: bool) {
-        // In test environment, we don't need to distinguish between cached and flushed state
+        // Persist cached writes. The cache is always emptied since flushed values
+        // are read back from STORAGE.
+        STORAGE_CACHE.with(|cache| {
+            STORAGE.with(|storage| {
+                storage.borrow_mut().extend(cache.borrow_mut().drain());
+            });
+        });
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn transient_load_bytes32(key: *const u8, dest: *mut u8) {
+        let key_slice = core::slice::from_raw_parts(key, 32);
+        let dest_slice = core::slice::from_raw_parts_mut(dest, 32);
+
+        TRANSIENT_STORAGE.with(|storage| {
+            match storage.borrow().get(key_slice) {
+                Some(value) => dest_slice.copy_from_slice(value),
+                None => dest_slice.fill(0),
+            };
+        });
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn transient_store_bytes32(key: *const u8, value: *const u8) {
+        let mut key_array = [0u8; 32];
+        key_array.copy_from_slice(core::slice::from_raw_parts(key, 32));
+
+        let mut value_array = [0u8; 32];
+        value_array.copy_from_slice(core::slice::from_raw_parts(value, 32));
+
+        TRANSIENT_STORAGE.with(|storage| {
+            storage.borrow_mut().insert(key_array, value_array);
+        });
     }
 
     #[no_mangle]
@@ -197,26 +319,55 @@ mod test_hooks {
 
     #[no_mangle]
     pub unsafe extern "C" fn msg_sender(sender: *mut u8) {
+        // The sender is stored as a left padded 32 byte word but the hostio
+        // writes a 20 byte address
         MSG_SENDER.with(|addr| {
-            let slice = core::slice::from_raw_parts_mut(sender, 32);
-            slice.copy_from_slice(&*addr.borrow());
+            let slice = core::slice::from_raw_parts_mut(sender, 20);
+            slice.copy_from_slice(&addr.borrow()[12..]);
+        });
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn account_balance(_address: *const u8, dest: *mut u8) {
+        ACCOUNT_BALANCE.with(|balance| {
+            let slice = core::slice::from_raw_parts_mut(dest, 32);
+            slice.copy_from_slice(&*balance.borrow());
         });
     }
 
     #[no_mangle]
     pub unsafe extern "C" fn call_contract(
+        contract: *const u8,
+        calldata: *const u8,
+        calldata_len: usize,
+        _value: *const u8,
+        _gas: u64,
+        return_data_len: *mut usize,
+    ) -> u8 {
+        CALL_COUNT.with(|count| *count.borrow_mut() += 1);
+        if !contract.is_null() {
+            let mut contract_address = [0u8; 20];
+            contract_address.copy_from_slice(core::slice::from_raw_parts(contract, 20));
+            let calldata = if calldata_len == 0 {
+                Vec::new()
+            } else {
+                core::slice::from_raw_parts(calldata, calldata_len).to_vec()
+            };
+            LAST_CALL.with(|last_call| *last_call.borrow_mut() = (contract_address, calldata));
+        }
+
+        simulate_call(return_data_len)
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn static_call_contract(
         _contract: *const u8,
         _calldata: *const u8,
         _calldata_len: usize,
-        _value: *const u8,
         _gas: u64,
         return_data_len: *mut usize,
     ) -> u8 {
-        RETURN_DATA.with(|return_data| {
-            let data = return_data.borrow();
-            *return_data_len = data.len();
-        });
-        0 // Indicate success
+        simulate_call(return_data_len)
     }
 
     #[no_mangle]
@@ -233,11 +384,66 @@ mod test_hooks {
             slice.len()
         })
     }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+        let bytes = core::slice::from_raw_parts(data, len);
+        let (topic_bytes, log_data) = bytes.split_at(topics * 32);
+        let topics = topic_bytes
+            .chunks_exact(32)
+            .map(|topic| topic.try_into().unwrap())
+            .collect();
+        LOGS.with(|logs| logs.borrow_mut().push((topics, log_data.to_vec())));
+    }
 }
 
 #[cfg(test)]
 pub use test_hooks::*;
 
+/// When the entrypoint flushes cached storage writes. See [`crate::FLUSH_POLICY`].
+///
+/// A failing call reverts every write of the batch under either policy, so the policy
+/// only affects gas. Flushing once per transaction writes a slot touched by several calls
+/// only once, which saves gas for batches that credit, lock or settle the same trader token
+/// repeatedly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// Flush after every handler that writes storage
+    PerHandler,
+
+    /// Flush once after every call in the batch succeeds
+    PerTransaction,
+}
+
+impl FlushPolicy {
+    /// Flush after a handler that wrote storage succeeded
+    pub fn after_handler(self) {
+        if self == FlushPolicy::PerHandler {
+            flush_storage();
+        }
+    }
+
+    /// Flush after every call in the batch succeeded
+    pub fn after_batch(self) {
+        if self == FlushPolicy::PerTransaction {
+            flush_storage();
+        }
+    }
+
+    /// Flush before a handler calls another contract. Required under either policy so
+    /// that re-entrant calls observe the handler's writes.
+    pub fn before_external_call(self) {
+        flush_storage();
+    }
+}
+
+/// Write cached storage writes to state and clear the cache
+pub fn flush_storage() {
+    unsafe {
+        storage_flush_cache(true);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,4 +515,25 @@ mod tests {
         assert_eq!(bytes_read, 2);
         assert_eq!(buffer, [0x34, 0x56]);
     }
+
+    #[test]
+    fn test_flush_policy() {
+        let key = [1u8; 32];
+        let value = [2u8; 32];
+
+        unsafe { storage_cache_bytes32(key.as_ptr(), value.as_ptr()) };
+        FlushPolicy::PerTransaction.after_handler();
+        assert!(get_flushed_storage_value(&key).is_none());
+
+        FlushPolicy::PerTransaction.after_batch();
+        assert_eq!(get_flushed_storage_value(&key), Some(value));
+
+        unsafe { storage_cache_bytes32(key.as_ptr(), [3u8; 32].as_ptr()) };
+        FlushPolicy::PerHandler.after_handler();
+        assert_eq!(get_flushed_storage_value(&key), Some([3u8; 32]));
+
+        unsafe { storage_cache_bytes32(key.as_ptr(), [4u8; 32].as_ptr()) };
+        FlushPolicy::PerTransaction.before_external_call();
+        assert_eq!(get_flushed_storage_value(&key), Some([4u8; 32]));
+    }
 }
@@ -0,0 +1,448 @@
+//! The selector table
+//!
+//! Every call the entrypoint accepts is described once in [`SELECTORS`], indexed by
+//! selector. The entrypoint dispatches through [`DISPATCH`] derived from it and the
+//! manifest returned by [`crate::getter::get_11_interface_version()`] is built from it,
+//! so the two can't drift apart. `test-vectors/goblin-core-interface.json` is generated from it for
+//! clients in other languages. Regenerate it with
+//!
+//! ```sh
+//! UPDATE_TEST_VECTORS=1 cargo test test_interface_json
+//! ```
+//!
+use crate::{getter::*, handler::*};
+
+/// Number of selectors in [`SELECTORS`]
+pub const NUM_SELECTORS: usize = 14;
+
+/// How a payload or return field is encoded
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum FieldKind {
+    /// 20 byte address
+    Address = 0,
+
+    /// Lots as a little endian u64
+    Lots = 1,
+
+    /// Big endian uint256, e.g. atoms
+    Uint256 = 2,
+
+    /// Single byte
+    Uint8 = 3,
+
+    /// Zero bytes inserted by `repr(C)` alignment
+    Padding = 4,
+
+    /// Opaque bytes with their own layout, e.g. the manifest
+    Bytes = 5,
+}
+
+/// A field of a payload or return value
+#[derive(Clone, Copy)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub len: usize,
+}
+
+impl Field {
+    const fn new(name: &'static str, kind: FieldKind, len: usize) -> Field {
+        Field { name, kind, len }
+    }
+
+    const fn address(name: &'static str) -> Field {
+        Field::new(name, FieldKind::Address, 20)
+    }
+
+    const fn lots(name: &'static str) -> Field {
+        Field::new(name, FieldKind::Lots, 8)
+    }
+
+    const fn uint256(name: &'static str) -> Field {
+        Field::new(name, FieldKind::Uint256, 32)
+    }
+
+    const fn uint8(name: &'static str) -> Field {
+        Field::new(name, FieldKind::Uint8, 1)
+    }
+
+    const fn padding(len: usize) -> Field {
+        Field::new("", FieldKind::Padding, len)
+    }
+}
+
+/// A call accepted by the entrypoint
+pub struct Selector {
+    pub selector: u8,
+    pub name: &'static str,
+    pub handler: fn(&[u8]) -> i32,
+
+    /// Whether the call writes storage. These calls hold the reentrancy lock and are
+    /// flushed according to [`crate::FLUSH_POLICY`].
+    pub writes_storage: bool,
+
+    /// Whether the call reads `msg_value`. At most one such call is accepted per batch.
+    pub payable: bool,
+
+    /// Payload length, which must match the handler's `repr(C)` params
+    pub payload_len: usize,
+
+    pub payload: &'static [Field],
+
+    pub returns: &'static [Field],
+}
+
+/// Balances are u128, split into the low words followed by the high words
+const TRADER_TOKEN_STATE: [Field; 4] = [
+    Field::lots("lots_locked"),
+    Field::lots("lots_free"),
+    Field::lots("lots_locked_high"),
+    Field::lots("lots_free_high"),
+];
+
+/// Every call, indexed by selector
+pub const SELECTORS: [Selector; NUM_SELECTORS] = [
+    Selector {
+        selector: HANDLE_0_CREDIT_ETH,
+        name: "credit_eth",
+        handler: handle_0_credit_eth,
+        writes_storage: true,
+        payable: true,
+        payload_len: HANDLE_0_PAYLOAD_LEN,
+        payload: &[Field::address("recipient")],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_1_CREDIT_ERC20,
+        name: "credit_erc20",
+        handler: handle_1_credit_erc20,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_1_PAYLOAD_LEN,
+        payload: &[
+            Field::address("token"),
+            Field::address("recipient"),
+            Field::lots("lots"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_2_WITHDRAW_ETH,
+        name: "withdraw_eth",
+        handler: handle_2_withdraw_eth,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_2_PAYLOAD_LEN,
+        payload: &[
+            Field::address("recipient"),
+            Field::padding(4),
+            Field::lots("lots"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_3_WITHDRAW_ERC20,
+        name: "withdraw_erc20",
+        handler: handle_3_withdraw_erc20,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_3_PAYLOAD_LEN,
+        payload: &[
+            Field::address("token"),
+            Field::address("recipient"),
+            Field::lots("lots"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_4_LOCK_FUNDS,
+        name: "lock_funds",
+        handler: handle_4_lock_funds,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_4_PAYLOAD_LEN,
+        payload: &[
+            Field::address("trader"),
+            Field::address("token"),
+            Field::lots("lots"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_5_UNLOCK_FUNDS,
+        name: "unlock_funds",
+        handler: handle_5_unlock_funds,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_5_PAYLOAD_LEN,
+        payload: &[
+            Field::address("trader"),
+            Field::address("token"),
+            Field::lots("lots"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_6_SETTLE_FUNDS,
+        name: "settle_funds",
+        handler: handle_6_settle_funds,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_6_PAYLOAD_LEN,
+        payload: &[
+            Field::address("token"),
+            Field::address("from"),
+            Field::address("to"),
+            Field::padding(4),
+            Field::lots("lots"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_7_SET_TRADER_CONFIG,
+        name: "set_trader_config",
+        handler: handle_7_set_trader_config,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_7_PAYLOAD_LEN,
+        payload: &[
+            Field::address("default_recipient"),
+            Field::uint8("settlement_hook"),
+            Field::uint8("operator_allowlist"),
+        ],
+        returns: &[],
+    },
+    Selector {
+        selector: HANDLE_8_CREDIT_ERC20_ATOMS,
+        name: "credit_erc20_atoms",
+        handler: handle_8_credit_erc20_atoms,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_8_PAYLOAD_LEN,
+        payload: &[
+            Field::address("token"),
+            Field::address("recipient"),
+            Field::uint256("atoms"),
+        ],
+        returns: &[Field::uint256("dust")],
+    },
+    Selector {
+        selector: HANDLE_9_WITHDRAW_ERC20_ATOMS,
+        name: "withdraw_erc20_atoms",
+        handler: handle_9_withdraw_erc20_atoms,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_9_PAYLOAD_LEN,
+        payload: &[
+            Field::address("token"),
+            Field::address("recipient"),
+            Field::uint256("atoms"),
+        ],
+        returns: &[Field::uint256("dust")],
+    },
+    Selector {
+        selector: GET_10_TRADER_TOKEN_STATE,
+        name: "get_trader_token_state",
+        handler: get_10_trader_token_state,
+        writes_storage: false,
+        payable: false,
+        payload_len: GET_10_PAYLOAD_LEN,
+        payload: &[Field::address("trader"), Field::address("token")],
+        returns: &TRADER_TOKEN_STATE,
+    },
+    Selector {
+        selector: GET_11_INTERFACE_VERSION,
+        name: "get_interface_version",
+        handler: get_11_interface_version,
+        writes_storage: false,
+        payable: false,
+        payload_len: GET_11_PAYLOAD_LEN,
+        payload: &[],
+        // The manifest length depends on this table, so it is filled in when the
+        // manifest is built
+        returns: &[Field::new("manifest", FieldKind::Bytes, 0)],
+    },
+    Selector {
+        selector: GET_12_TOKEN_PREFLIGHT,
+        name: "get_token_preflight",
+        handler: get_12_token_preflight,
+        writes_storage: false,
+        payable: false,
+        payload_len: GET_12_PAYLOAD_LEN,
+        payload: &[Field::address("trader"), Field::address("token")],
+        returns: &[
+            Field::uint256("balance"),
+            Field::uint256("allowance"),
+            TRADER_TOKEN_STATE[0],
+            TRADER_TOKEN_STATE[1],
+            TRADER_TOKEN_STATE[2],
+            TRADER_TOKEN_STATE[3],
+        ],
+    },
+    Selector {
+        selector: HANDLE_13_SET_REGISTRY_ENTRY,
+        name: "set_registry_entry",
+        handler: handle_13_set_registry_entry,
+        writes_storage: true,
+        payable: false,
+        payload_len: HANDLE_13_PAYLOAD_LEN,
+        payload: &[
+            Field::address("account"),
+            Field::uint8("list"),
+            Field::uint8("registered"),
+        ],
+        returns: &[],
+    },
+];
+
+/// The part of a [`Selector`] read by the entrypoint. Only this is kept in the binary,
+/// names and field layouts are compiled into [`crate::getter::MANIFEST`].
+pub struct Dispatch {
+    pub handler: fn(&[u8]) -> i32,
+    pub payload_len: u16,
+    pub writes_storage: bool,
+    pub payable: bool,
+}
+
+/// Entrypoint view of [`SELECTORS`], indexed by selector
+pub const DISPATCH: [Dispatch; NUM_SELECTORS] = build_dispatch();
+
+const fn build_dispatch() -> [Dispatch; NUM_SELECTORS] {
+    let mut dispatch = [const {
+        Dispatch {
+            handler: |_| 1,
+            payload_len: 0,
+            writes_storage: false,
+            payable: false,
+        }
+    }; NUM_SELECTORS];
+
+    let mut i = 0;
+    while i < NUM_SELECTORS {
+        let entry = &SELECTORS[i];
+        dispatch[i] = Dispatch {
+            handler: entry.handler,
+            payload_len: entry.payload_len as u16,
+            writes_storage: entry.writes_storage,
+            payable: entry.payable,
+        };
+        i += 1;
+    }
+    dispatch
+}
+
+/// Sum of the field lengths
+pub const fn fields_len(fields: &[Field]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < fields.len() {
+        len += fields[i].len;
+        i += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::getter::{INTERFACE_VERSION, MANIFEST_LEN};
+
+    const INTERFACE_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test-vectors/goblin-core-interface.json"
+    );
+
+    #[test]
+    fn test_selectors_are_indexed_by_selector() {
+        for (i, entry) in SELECTORS.iter().enumerate() {
+            assert_eq!(entry.selector as usize, i, "{}", entry.name);
+        }
+    }
+
+    #[test]
+    fn test_fields_match_payload_len() {
+        for entry in &SELECTORS {
+            assert_eq!(
+                fields_len(entry.payload),
+                entry.payload_len,
+                "{}",
+                entry.name
+            );
+        }
+
+        assert_eq!(
+            fields_len(&TRADER_TOKEN_STATE),
+            core::mem::size_of::<crate::state::TraderTokenState>()
+        );
+        assert_eq!(
+            fields_len(SELECTORS[GET_12_TOKEN_PREFLIGHT as usize].returns),
+            core::mem::size_of::<TokenPreflight>()
+        );
+    }
+
+    fn fields_to_json(fields: &[Field]) -> String {
+        let fields = fields
+            .iter()
+            .map(|field| {
+                // The manifest length is only known once the table is built
+                let len = match field.kind {
+                    FieldKind::Bytes => MANIFEST_LEN,
+                    _ => field.len,
+                };
+                format!(
+                    "{{ \"name\": \"{}\", \"kind\": \"{:?}\", \"len\": {} }}",
+                    field.name, field.kind, len
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if fields.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n        {}\n      ]", fields.join(",\n        "))
+        }
+    }
+
+    fn to_json() -> String {
+        let mut json = format!(
+            "{{\n  \"version\": {},\n  \"selectors\": [\n",
+            INTERFACE_VERSION
+        );
+
+        for (i, entry) in SELECTORS.iter().enumerate() {
+            json += "    {\n";
+            json += &format!("      \"selector\": {},\n", entry.selector);
+            json += &format!("      \"name\": \"{}\",\n", entry.name);
+            json += &format!("      \"writes_storage\": {},\n", entry.writes_storage);
+            json += &format!("      \"payable\": {},\n", entry.payable);
+            json += &format!("      \"payload_len\": {},\n", entry.payload_len);
+            json += &format!("      \"payload\": {},\n", fields_to_json(entry.payload));
+            json += &format!("      \"returns\": {}\n", fields_to_json(entry.returns));
+            json += if i + 1 < SELECTORS.len() {
+                "    },\n"
+            } else {
+                "    }\n"
+            };
+        }
+
+        json += "  ]\n}\n";
+        json
+    }
+
+    #[test]
+    fn test_interface_json() {
+        let json = to_json();
+
+        if std::env::var("UPDATE_TEST_VECTORS").is_ok() {
+            std::fs::write(INTERFACE_PATH, &json).unwrap();
+        }
+
+        let expected = std::fs::read_to_string(INTERFACE_PATH).unwrap();
+        assert_eq!(
+            json, expected,
+            "interface manifest is stale, see src/interface.rs"
+        );
+    }
+}
@@ -2,21 +2,29 @@
 #![cfg_attr(not(test), no_main)]
 
 use core::mem::MaybeUninit;
-use getter::{get_10_trader_token_state, GET_10_PAYLOAD_LEN, GET_10_TRADER_TOKEN_STATE};
-use handler::{
-    handle_0_credit_eth, handle_1_credit_erc20, HANDLE_0_CREDIT_ETH, HANDLE_0_PAYLOAD_LEN,
-    HANDLE_1_CREDIT_ERC20, HANDLE_1_PAYLOAD_LEN,
-};
 use hostio::*;
 
+#[cfg(feature = "abi")]
+pub mod abi;
 pub mod erc20;
+pub mod eth;
+pub mod events;
 pub mod getter;
+pub mod guard;
 pub mod handler;
 pub mod hostio;
+pub mod interface;
 pub mod market_params;
 pub mod quantities;
+pub mod registry;
+pub mod settlement_hook;
 pub mod state;
+#[cfg(test)]
+mod test_fixtures;
+#[cfg(test)]
+mod test_vectors;
 pub mod types;
+pub mod withdrawal;
 
 // Address 0xa6e41ffd769491a42a6e5ce453259b93983a22ef
 // Deployer 0x3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E, nonce 0
@@ -25,58 +33,119 @@ pub const ADDRESS: [u8; 20] = [
     166, 228, 31, 253, 118, 148, 145, 164, 42, 110, 92, 228, 83, 37, 155, 147, 152, 58, 34, 239,
 ];
 
+/// Account allowed to manage the [`registry`] lists, the deployer
+pub const OWNER: [u8; 20] = [
+    63, 30, 174, 125, 70, 216, 143, 8, 252, 47, 142, 210, 127, 203, 42, 177, 131, 235, 45, 14,
+];
+
+/// Maximum calldata length. Longer inputs are rejected since arguments are read into
+/// a fixed size stack buffer. The contract has no allocator to grow it.
+pub const MAX_ARGS_LEN: usize = 512;
+
+/// Storage writes are flushed once after every call of the batch succeeds.
+///
+/// A failing call makes the entrypoint return nonzero, which reverts every write of the
+/// batch. Handlers that call other contracts still flush before the call with
+/// [`FlushPolicy::before_external_call`] so that re-entrant calls observe their writes.
+pub const FLUSH_POLICY: FlushPolicy = FlushPolicy::PerTransaction;
+
+/// Entrypoint for a batch of calls
+///
+/// # Wire format
+///
+/// ```text
+/// header (1 byte) | call_0 | call_1 | ...
+///
+/// call = selector (1 byte) | payload_len (u16, little endian) | payload (payload_len bytes)
+/// ```
+///
+/// * Every call is length prefixed, so a truncated or mis-encoded call is rejected
+///   before its handler reads the payload.
+///
+/// * Every handler has a fixed `repr(C)` payload layout and rejects any other length.
+///   The prefix leaves room for variable length payloads but no handler accepts one yet.
+///
+/// * Selectors are dispatched through [`interface::DISPATCH`], generated from the
+///   selector table that also builds the interface manifest.
+///
 #[no_mangle]
 pub extern "C" fn user_entrypoint(len: usize) -> i32 {
-    if len == 0 {
+    if len == 0 || len > MAX_ARGS_LEN {
         return 1;
     }
 
-    let mut input = MaybeUninit::<[u8; 512]>::uninit();
+    let mut input = MaybeUninit::<[u8; MAX_ARGS_LEN]>::uninit();
     let input = unsafe {
         read_args(input.as_mut_ptr() as *mut u8);
-        input.assume_init_ref()
+        input.assume_init_mut()
+    };
+
+    // Solidity ABI calls are rewritten into a single call batch
+    #[cfg(feature = "abi")]
+    let len = match abi::translate(input, len) {
+        abi::Translation::NotAbi => len,
+        abi::Translation::Batch(len) => len,
+        abi::Translation::Invalid => return 1,
     };
 
     let num_calls = input[0] as usize;
     let mut offset = 1;
 
+    // Every payable call reads the same `msg_value`, so only one is allowed per batch
+    let mut msg_value_consumed = false;
+
     for _ in 0..num_calls {
-        // Invalid input: not enough bytes for selector
-        if offset >= len {
+        // Invalid input: not enough bytes for selector and payload length
+        if offset + 3 > len {
             return 1;
         }
 
         let selector = input[offset];
-        offset += 1;
+        let payload_len = u16::from_le_bytes([input[offset + 1], input[offset + 2]]) as usize;
+        offset += 3;
 
-        let payload_len = match selector {
-            HANDLE_0_CREDIT_ETH => HANDLE_0_PAYLOAD_LEN,
-            HANDLE_1_CREDIT_ERC20 => HANDLE_1_PAYLOAD_LEN,
-            GET_10_TRADER_TOKEN_STATE => GET_10_PAYLOAD_LEN,
-            _ => return 1, // Unknown selector
+        let Some(entry) = interface::DISPATCH.get(selector as usize) else {
+            return 1; // Unknown selector
         };
 
-        if offset + payload_len > len {
-            // Invalid input: payload out of bounds
+        // Invalid input: payload out of bounds or of the wrong size
+        if offset + payload_len > len || payload_len != entry.payload_len as usize {
             return 1;
         }
 
+        if entry.payable {
+            if msg_value_consumed {
+                return 1;
+            }
+            msg_value_consumed = true;
+        }
+
         let payload = &input[offset..offset + payload_len];
         offset += payload_len;
 
-        let result = match selector {
-            HANDLE_0_CREDIT_ETH => handle_0_credit_eth(payload),
-            HANDLE_1_CREDIT_ERC20 => handle_1_credit_erc20(payload),
-            GET_10_TRADER_TOKEN_STATE => get_10_trader_token_state(payload),
-            _ => return 1,
-        };
+        // Handlers can't be re-entered through external calls they make. Getters are
+        // not locked so they remain usable from static calls.
+        if entry.writes_storage && guard::lock() != 0 {
+            return 1;
+        }
 
-        // If any handler fails (returns nonzero), propagate the error
+        let result = (entry.handler)(payload);
+
+        // If any handler fails (returns nonzero), propagate the error. Cached writes
+        // are not flushed.
         if result != 0 {
             return result;
         }
+
+        // Getters don't write to storage and need no flush
+        if entry.writes_storage {
+            guard::unlock();
+            FLUSH_POLICY.after_handler();
+        }
     }
 
+    FLUSH_POLICY.after_batch();
+
     0
 }
 
@@ -91,3 +160,152 @@ pub unsafe extern "C" fn mark_used() {
     pay_for_memory_grow(0);
     panic!();
 }
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use hex_literal::hex;
+
+    use super::*;
+    use crate::{
+        handler::*,
+        quantities::Lots,
+        state::{SlotKey, SlotState, TraderTokenKey, TraderTokenState},
+        types::NATIVE_TOKEN,
+    };
+
+    const TRADER: [u8; 20] = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+
+    /// Credit 1 lot of ETH to TRADER, then try to withdraw 2 lots of an ERC20
+    /// that TRADER never deposited. The second call fails.
+    fn credit_then_failing_withdraw_args(header: u8) -> Vec<u8> {
+        let mut test_args: Vec<u8> = vec![header];
+
+        test_args.push(HANDLE_0_CREDIT_ETH);
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&TRADER);
+
+        test_args.push(HANDLE_3_WITHDRAW_ERC20);
+        test_args.extend_from_slice(&(HANDLE_3_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a"));
+        test_args.extend_from_slice(&TRADER);
+        test_args.extend_from_slice(&2u64.to_le_bytes());
+
+        test_args
+    }
+
+    fn set_up_hostios() {
+        crate::registry::set_operator_sender(&TRADER);
+
+        // 10^6 wei = 1 lot
+        set_msg_value(hex!(
+            "00000000000000000000000000000000000000000000000000000000000F4240"
+        ));
+    }
+
+    fn eth_key() -> TraderTokenKey {
+        TraderTokenKey {
+            trader: TRADER,
+            token: NATIVE_TOKEN,
+        }
+    }
+
+    #[test]
+    fn test_failed_batch_is_not_flushed() {
+        set_up_hostios();
+
+        let test_args = credit_then_failing_withdraw_args(2);
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 1);
+
+        assert!(get_flushed_storage_value(&eth_key().to_keccak256()).is_none());
+    }
+
+    #[test]
+    fn test_batch_is_flushed_on_success() {
+        set_up_hostios();
+
+        // Credit ETH, then set a trader config
+        let mut test_args: Vec<u8> = vec![2];
+        test_args.push(HANDLE_0_CREDIT_ETH);
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&TRADER);
+        test_args.push(HANDLE_7_SET_TRADER_CONFIG);
+        test_args.extend_from_slice(&(HANDLE_7_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&[0u8; HANDLE_7_PAYLOAD_LEN]);
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 0);
+
+        let key = &eth_key();
+        assert!(get_flushed_storage_value(&key.to_keccak256()).is_some());
+
+        let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+        let trader_token_state =
+            unsafe { TraderTokenState::load(key, &mut trader_token_state_maybe) };
+        assert_eq!(trader_token_state.lots_free, Lots(1));
+    }
+
+    #[test]
+    fn test_reject_second_eth_credit_in_batch() {
+        set_up_hostios();
+
+        // Both credits would read the same msg_value of 1 lot
+        let mut test_args: Vec<u8> = vec![2];
+        for _ in 0..2 {
+            test_args.push(HANDLE_0_CREDIT_ETH);
+            test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+            test_args.extend_from_slice(&TRADER);
+        }
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 1);
+
+        assert!(get_flushed_storage_value(&eth_key().to_keccak256()).is_none());
+    }
+
+    #[test]
+    fn test_reject_wrong_payload_len() {
+        set_up_hostios();
+
+        // Declared length is one byte short of an address
+        let mut test_args: Vec<u8> = vec![1, HANDLE_0_CREDIT_ETH];
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16 - 1).to_le_bytes());
+        test_args.extend_from_slice(&TRADER[..19]);
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 1);
+    }
+
+    #[test]
+    fn test_reject_payload_out_of_bounds() {
+        set_up_hostios();
+
+        // Declared length is correct but the payload is truncated
+        let mut test_args: Vec<u8> = vec![1, HANDLE_0_CREDIT_ETH];
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&TRADER[..19]);
+        set_test_args(test_args.clone());
+        assert_eq!(user_entrypoint(test_args.len()), 1);
+    }
+
+    #[test]
+    fn test_reject_args_longer_than_buffer() {
+        assert_eq!(user_entrypoint(MAX_ARGS_LEN + 1), 1);
+    }
+
+    #[test]
+    fn test_reentrant_call_is_rejected() {
+        set_up_hostios();
+
+        let mut test_args: Vec<u8> = vec![1, HANDLE_0_CREDIT_ETH];
+        test_args.extend_from_slice(&(HANDLE_0_PAYLOAD_LEN as u16).to_le_bytes());
+        test_args.extend_from_slice(&TRADER);
+        set_test_args(test_args.clone());
+
+        // Simulate an outer handler holding the lock
+        assert_eq!(guard::lock(), 0);
+        assert_eq!(user_entrypoint(test_args.len()), 1);
+
+        guard::unlock();
+        assert_eq!(user_entrypoint(test_args.len()), 0);
+    }
+}
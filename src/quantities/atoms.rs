@@ -1,4 +1,4 @@
-use super::{Lots, HIGH_LOTS_SCALE};
+use super::Lots;
 
 /// The number of atoms as `U256` in **big endian**. It represents the amount of wei or
 /// the amount of ERC20 tokens.
@@ -21,6 +21,70 @@ impl Atoms {
     pub fn to_be_bytes(&self) -> &[u8; 32] {
         unsafe { &*(self.0.as_ptr() as *const [u8; 32]) }
     }
+
+    /// Subtract `rhs` from `self`. Returns None on underflow.
+    ///
+    /// Words are swapped to native endianness, subtracted from the least significant
+    /// word (index 3) upwards with borrow, then swapped back.
+    pub fn checked_sub(&self, rhs: &Atoms) -> Option<Atoms> {
+        let mut result = [0u64; 4];
+        let mut borrow = false;
+
+        for i in (0..4).rev() {
+            let (diff, borrow_1) = self.0[i]
+                .swap_bytes()
+                .overflowing_sub(rhs.0[i].swap_bytes());
+            let (diff, borrow_2) = diff.overflowing_sub(borrow as u64);
+            result[i] = diff.swap_bytes();
+            borrow = borrow_1 || borrow_2;
+        }
+
+        if borrow {
+            None
+        } else {
+            Some(Atoms(result))
+        }
+    }
+
+    /// Divide the lower two words by 10^6. Returns the high and low words of the
+    /// quotient and the remainder.
+    ///
+    /// Long division over 32 bit digits, so that only u64 arithmetic is used. A u128
+    /// division would pull a large routine into the binary. Every partial remainder is
+    /// below 10^6 < 2^20, so shifting it by 32 bits can't overflow.
+    pub fn div_lot_size(&self) -> (u64, u64, u64) {
+        const LOT_SIZE: u64 = 1_000_000;
+
+        let high = self.0[2].swap_bytes();
+        let low = self.0[3].swap_bytes();
+
+        let quotient_high = high / LOT_SIZE;
+
+        let digit = ((high % LOT_SIZE) << 32) | (low >> 32);
+        let quotient_mid = digit / LOT_SIZE;
+
+        let digit = ((digit % LOT_SIZE) << 32) | (low & 0xffff_ffff);
+        let quotient_low = digit / LOT_SIZE;
+
+        (
+            quotient_high,
+            (quotient_mid << 32) | quotient_low,
+            digit % LOT_SIZE,
+        )
+    }
+
+    /// Split atoms into whole lots and the remaining dust, such that
+    /// `Atoms::from(&lots) + dust == self`. Lots are rounded down.
+    ///
+    /// Returns None if the lots don't fit in u64.
+    pub fn split_lots(&self) -> Option<(Lots, Atoms)> {
+        let (lots_high, lots, dust) = self.div_lot_size();
+        if self.0[0] != 0 || self.0[1] != 0 || lots_high != 0 {
+            return None;
+        }
+
+        Some((Lots(lots), Atoms([0, 0, 0, dust.swap_bytes()])))
+    }
 }
 
 impl From<&Lots> for Atoms {
@@ -28,24 +92,20 @@ impl From<&Lots> for Atoms {
     ///
     /// * Lots are stored in little endian format while Atoms are in big endian
     /// * 1 lot = 10^6 atoms
-    /// * The conversion preserves the relationship: from_lots(to_lots(atoms)) == atoms
-    ///   for values within the supported range
+    /// * The conversion is exact. u64::MAX lots need less than 2^84 atoms, so the
+    ///   product always fits in the lower two words.
     ///
     /// # Formula
     /// * Input: lots in little endian
-    /// * For the high word: lots / HIGH_LOTS_SCALE (where HIGH_LOTS_SCALE = 2^64 / 10^6)
-    /// * For the low word: (lots % HIGH_LOTS_SCALE) * 10^6
+    /// * atoms = lots * 10^6 as u128
+    /// * The high word holds atoms >> 64 and the low word the lower 64 bits
     /// * Convert both to big endian by swapping bytes
     /// * Store in [u64; 4] array
     fn from(lots: &Lots) -> Self {
-        let lots_value = lots.0;
+        let atoms = lots.0 as u128 * 1_000_000;
 
-        // Split into high and low components using HIGH_LOTS_SCALE
-        // lots = high * HIGH_LOTS_SCALE + low / 10^6
-        // - high = lots / HIGH_LOTS_SCALE
-        // - lot = remainder of above division * 10^6
-        let high = lots_value / HIGH_LOTS_SCALE;
-        let low = (lots_value % HIGH_LOTS_SCALE) * 1_000_000;
+        let high = (atoms >> 64) as u64;
+        let low = atoms as u64;
 
         // Convert to big endian format
         Atoms([
@@ -71,9 +131,81 @@ mod tests {
         assert_eq!(*bytes, expected_bytes);
     }
 
+    #[test]
+    fn test_checked_sub() {
+        let a = Atoms([0, 0, 1u64.swap_bytes(), 5u64.swap_bytes()]);
+        let b = Atoms([0, 0, 0, 7u64.swap_bytes()]);
+
+        // (2^64 + 5) - 7 = 2^64 - 2, borrowing from word 2
+        let diff = a.checked_sub(&b).unwrap();
+        assert_eq!(diff.0, [0, 0, 0, (u64::MAX - 1).swap_bytes()]);
+
+        assert!(b.checked_sub(&a).is_none());
+        assert_eq!(a.checked_sub(&a).unwrap().0, [0; 4]);
+    }
+
+    #[test]
+    fn test_div_lot_size() {
+        for atoms in [
+            0u128,
+            999_999,
+            1_000_000,
+            (1 << 64) + 999_999,
+            u64::MAX as u128 * 1_000_000 + 999_999,
+            u64::MAX as u128 * 1_000_000 + 1_000_000,
+            u128::MAX,
+        ] {
+            let (high, low, rem) = Atoms([
+                0,
+                0,
+                ((atoms >> 64) as u64).swap_bytes(),
+                (atoms as u64).swap_bytes(),
+            ])
+            .div_lot_size();
+
+            assert_eq!(((high as u128) << 64) | low as u128, atoms / 1_000_000);
+            assert_eq!(rem as u128, atoms % 1_000_000);
+        }
+    }
+
+    #[test]
+    fn test_split_lots() {
+        let (lots, dust) = Atoms([0, 0, 0, 2_500_000u64.swap_bytes()])
+            .split_lots()
+            .unwrap();
+        assert_eq!(lots, Lots(2));
+        assert_eq!(dust.0, [0, 0, 0, 500_000u64.swap_bytes()]);
+
+        // 2^64 + 999_999 = 18446744073710551615 atoms
+        let (lots, dust) = Atoms([0, 0, 1u64.swap_bytes(), 999_999u64.swap_bytes()])
+            .split_lots()
+            .unwrap();
+        assert_eq!(lots, Lots(18446744073710));
+        assert_eq!(dust.0, [0, 0, 0, 551_615u64.swap_bytes()]);
+
+        // The largest amount that fits
+        let max = Atoms::from(&Lots(u64::MAX));
+        let max_plus_dust = Atoms([
+            0,
+            0,
+            max.0[2],
+            (max.0[3].swap_bytes() + 999_999).swap_bytes(),
+        ]);
+        let (lots, dust) = max_plus_dust.split_lots().unwrap();
+        assert_eq!(lots, Lots(u64::MAX));
+        assert_eq!(dust.0, [0, 0, 0, 999_999u64.swap_bytes()]);
+
+        assert!(Atoms([0, 1u64.swap_bytes(), 0, 0]).split_lots().is_none());
+        assert!(Atoms([0, 0, 1_000_000u64.swap_bytes(), 0])
+            .split_lots()
+            .is_none());
+    }
+
     mod test_atom_to_lot_conversions {
         use super::*;
 
+        use crate::quantities::HIGH_LOTS_SCALE;
+
         #[test]
         fn test_basic_conversion() {
             let zero_lots = Lots(0);
@@ -95,17 +227,22 @@ mod tests {
 
         #[test]
         fn test_large_values() {
-            // Test with SCALE value
+            // HIGH_LOTS_SCALE * 10^6 is just below 2^64
             let scale_lots = Lots(HIGH_LOTS_SCALE);
             let atoms = Atoms::from(&scale_lots);
-            assert_eq!(atoms.0[2].swap_bytes(), 1);
-            assert_eq!(atoms.0[3], 0);
+            assert_eq!(atoms.0[2], 0);
+            assert_eq!(atoms.0[3].swap_bytes(), 18_446_744_073_709_000_000);
 
-            // Test with SCALE + 1
+            // (HIGH_LOTS_SCALE + 1) * 10^6 = 2^64 + 448_384
             let scale_plus_one = Lots(HIGH_LOTS_SCALE + 1);
             let atoms = Atoms::from(&scale_plus_one);
             assert_eq!(atoms.0[2].swap_bytes(), 1);
-            assert_eq!(atoms.0[3].swap_bytes(), 1_000_000);
+            assert_eq!(atoms.0[3].swap_bytes(), 448_384);
+
+            let atoms = Atoms::from(&Lots(u64::MAX));
+            let expected = u64::MAX as u128 * 1_000_000;
+            assert_eq!(atoms.0[2].swap_bytes(), (expected >> 64) as u64);
+            assert_eq!(atoms.0[3].swap_bytes(), expected as u64);
         }
 
         #[test]
@@ -115,6 +252,10 @@ mod tests {
             let atoms = Atoms::from(&original_lots);
             let roundtrip_lots = Lots::from(&atoms);
             assert_eq!(original_lots.0, roundtrip_lots.0);
+
+            for lots in [HIGH_LOTS_SCALE, HIGH_LOTS_SCALE + 1, u64::MAX] {
+                assert_eq!(Lots::from(&Atoms::from(&Lots(lots))), Lots(lots));
+            }
         }
     }
 }
@@ -0,0 +1,21 @@
+//! Checked arithmetic between quantities
+//!
+//! * Multiplication and division are defined between types, e.g. `BaseLots *
+//!   BaseAtomsPerBaseLot = BaseAtoms`, so they are traits rather than inherent methods.
+//!
+//! * Failures return None instead of panicking. The panic handler loops until the call
+//!   runs out of gas, so handlers must map None to a failing return code.
+//!
+/// Multiply, returning None on overflow
+pub trait CheckedMul<Rhs = Self> {
+    type Output;
+
+    fn checked_mul(self, rhs: Rhs) -> Option<Self::Output>;
+}
+
+/// Divide, returning None if `rhs` is zero or the quotient doesn't fit in the output type
+pub trait CheckedDiv<Rhs = Self> {
+    type Output;
+
+    fn checked_div(self, rhs: Rhs) -> Option<Self::Output>;
+}
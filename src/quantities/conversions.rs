@@ -0,0 +1,269 @@
+//! Checked conversions between atoms and lots
+//!
+//! * Atoms are raw token units. A market groups them into lots of `BaseAtomsPerBaseLot`
+//!   and `QuoteAtomsPerQuoteLot` atoms.
+//!
+//! * Every conversion returns None on overflow or division by zero instead of wrapping.
+//!
+//! * Atoms to lots conversions take a [`Rounding`] direction. Round down amounts owed to
+//!   traders and round up amounts owed by traders, so that dust never favors the trader.
+//!
+//! * Base and quote lots convert at a price in ticks. The price of one base unit is
+//!   `price_in_ticks * tick_size` quote lots, and a base unit holds
+//!   `base_lots_per_base_unit` base lots.
+//!
+use crate::define_lot_conversions;
+
+use super::{
+    BaseAtoms, BaseAtomsPerBaseLot, BaseLots, BaseLotsPerBaseUnit, QuoteAtoms,
+    QuoteAtomsPerQuoteLot, QuoteLots, QuoteLotsPerBaseUnitPerTick, Ticks,
+};
+
+/// Rounding direction for divisions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Compute `a * b / denominator` with a 128 bit intermediate product.
+///
+/// Returns None if `denominator` is zero or the result does not fit in u64.
+pub fn mul_div(a: u64, b: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+
+    let product = a as u128 * b as u128;
+    let denominator = denominator as u128;
+
+    let result = match rounding {
+        Rounding::Down => product / denominator,
+        Rounding::Up => product.div_ceil(denominator),
+    };
+
+    u64::try_from(result).ok()
+}
+
+/// Atoms per lot for a token whose lowest `decimals_to_ignore` decimals are not traded,
+/// i.e. `10^decimals_to_ignore`. Returns None if the value does not fit in u64.
+pub fn atoms_per_lot_from_decimals(decimals_to_ignore: u8) -> Option<u64> {
+    10u64.checked_pow(decimals_to_ignore as u32)
+}
+
+define_lot_conversions!(BaseLots, BaseAtomsPerBaseLot, BaseAtoms);
+define_lot_conversions!(QuoteLots, QuoteAtomsPerQuoteLot, QuoteAtoms);
+
+/// Quote lots per base unit at `price_in_ticks`. Returns None on overflow.
+fn quote_lots_per_base_unit(
+    price_in_ticks: Ticks,
+    tick_size: QuoteLotsPerBaseUnitPerTick,
+) -> Option<u64> {
+    (price_in_ticks.0 as u64).checked_mul(tick_size.0)
+}
+
+/// Value of `base_lots` in quote lots at `price_in_ticks`, i.e.
+/// `base_lots * price_in_ticks * tick_size / base_lots_per_base_unit`.
+///
+/// Returns None on overflow or if `base_lots_per_base_unit` is zero.
+pub fn base_lots_to_quote_lots_at_price(
+    base_lots: BaseLots,
+    price_in_ticks: Ticks,
+    tick_size: QuoteLotsPerBaseUnitPerTick,
+    base_lots_per_base_unit: BaseLotsPerBaseUnit,
+    rounding: Rounding,
+) -> Option<QuoteLots> {
+    let price = quote_lots_per_base_unit(price_in_ticks, tick_size)?;
+    mul_div(base_lots.0, price, base_lots_per_base_unit.0, rounding).map(QuoteLots)
+}
+
+/// Base lots that `quote_lots` are worth at `price_in_ticks`, i.e.
+/// `quote_lots * base_lots_per_base_unit / (price_in_ticks * tick_size)`.
+///
+/// Returns None on overflow or if the price is zero.
+pub fn quote_lots_to_base_lots_at_price(
+    quote_lots: QuoteLots,
+    price_in_ticks: Ticks,
+    tick_size: QuoteLotsPerBaseUnitPerTick,
+    base_lots_per_base_unit: BaseLotsPerBaseUnit,
+    rounding: Rounding,
+) -> Option<BaseLots> {
+    let price = quote_lots_per_base_unit(price_in_ticks, tick_size)?;
+    mul_div(quote_lots.0, base_lots_per_base_unit.0, price, rounding).map(BaseLots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(10, 3, 4, Rounding::Down), Some(7));
+        assert_eq!(mul_div(10, 3, 4, Rounding::Up), Some(8));
+
+        // Exact divisions are not rounded up
+        assert_eq!(mul_div(10, 4, 4, Rounding::Up), Some(10));
+
+        // The intermediate product may exceed u64
+        assert_eq!(
+            mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down),
+            Some(u64::MAX)
+        );
+
+        assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_atoms_per_lot_from_decimals() {
+        assert_eq!(atoms_per_lot_from_decimals(0), Some(1));
+        assert_eq!(atoms_per_lot_from_decimals(6), Some(1_000_000));
+        assert_eq!(atoms_per_lot_from_decimals(19), Some(10u64.pow(19)));
+        assert_eq!(atoms_per_lot_from_decimals(20), None);
+    }
+
+    #[test]
+    fn test_lots_to_atoms() {
+        let atoms_per_lot = BaseAtomsPerBaseLot(1_000);
+
+        assert_eq!(
+            BaseLots(5).checked_to_atoms(atoms_per_lot),
+            Some(BaseAtoms(5_000))
+        );
+        assert_eq!(BaseLots(u64::MAX).checked_to_atoms(atoms_per_lot), None);
+    }
+
+    #[test]
+    fn test_atoms_to_lots() {
+        let atoms_per_lot = QuoteAtomsPerQuoteLot(1_000);
+
+        assert_eq!(
+            QuoteAtoms(2_500).checked_to_lots(atoms_per_lot, Rounding::Down),
+            Some(QuoteLots(2))
+        );
+        assert_eq!(
+            QuoteAtoms(2_500).checked_to_lots(atoms_per_lot, Rounding::Up),
+            Some(QuoteLots(3))
+        );
+        assert_eq!(
+            QuoteAtoms(2_000).checked_to_lots(atoms_per_lot, Rounding::Up),
+            Some(QuoteLots(2))
+        );
+        assert_eq!(
+            QuoteAtoms(1).checked_to_lots(QuoteAtomsPerQuoteLot(0), Rounding::Down),
+            None
+        );
+    }
+
+    #[test]
+    fn test_base_lots_to_quote_lots_at_price() {
+        // 1 base unit = 100 base lots, priced at 50 ticks * 2 quote lots per tick
+        let tick_size = QuoteLotsPerBaseUnitPerTick(2);
+        let base_lots_per_base_unit = BaseLotsPerBaseUnit(100);
+
+        assert_eq!(
+            base_lots_to_quote_lots_at_price(
+                BaseLots(300),
+                Ticks(50),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Down
+            ),
+            Some(QuoteLots(300))
+        );
+
+        // 1 base lot at 102 quote lots per base unit is worth 1.02 quote lots
+        assert_eq!(
+            base_lots_to_quote_lots_at_price(
+                BaseLots(1),
+                Ticks(51),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Down
+            ),
+            Some(QuoteLots(1))
+        );
+        assert_eq!(
+            base_lots_to_quote_lots_at_price(
+                BaseLots(1),
+                Ticks(51),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Up
+            ),
+            Some(QuoteLots(2))
+        );
+
+        assert_eq!(
+            base_lots_to_quote_lots_at_price(
+                BaseLots(u64::MAX),
+                Ticks(u32::MAX),
+                QuoteLotsPerBaseUnitPerTick(u64::MAX),
+                base_lots_per_base_unit,
+                Rounding::Down
+            ),
+            None
+        );
+        assert_eq!(
+            base_lots_to_quote_lots_at_price(
+                BaseLots(1),
+                Ticks(1),
+                tick_size,
+                BaseLotsPerBaseUnit(0),
+                Rounding::Down
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_quote_lots_to_base_lots_at_price() {
+        let tick_size = QuoteLotsPerBaseUnitPerTick(2);
+        let base_lots_per_base_unit = BaseLotsPerBaseUnit(100);
+
+        assert_eq!(
+            quote_lots_to_base_lots_at_price(
+                QuoteLots(300),
+                Ticks(50),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Down
+            ),
+            Some(BaseLots(300))
+        );
+
+        // 101 quote lots at 102 quote lots per base unit buy 99.01 base lots
+        assert_eq!(
+            quote_lots_to_base_lots_at_price(
+                QuoteLots(101),
+                Ticks(51),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Down
+            ),
+            Some(BaseLots(99))
+        );
+        assert_eq!(
+            quote_lots_to_base_lots_at_price(
+                QuoteLots(101),
+                Ticks(51),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Up
+            ),
+            Some(BaseLots(100))
+        );
+
+        // Zero price
+        assert_eq!(
+            quote_lots_to_base_lots_at_price(
+                QuoteLots(1),
+                Ticks(0),
+                tick_size,
+                base_lots_per_base_unit,
+                Rounding::Down
+            ),
+            None
+        );
+    }
+}
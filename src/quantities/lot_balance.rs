@@ -0,0 +1,73 @@
+//! The lots held by a trader
+//!
+//! * Amounts in calls and events are [`Lots`], a u64. Balances accumulate many of them
+//!   and use u128 so that tokens with many decimals can't overflow realistic balances.
+//!
+//! * A balance is stored as two little endian u64 words, see
+//!   [`crate::state::TraderTokenState`].
+//!
+use crate::define_custom_types;
+
+use super::Lots;
+
+define_custom_types!(LotBalance<u128>);
+
+impl LotBalance {
+    /// Join the high and low words of a stored balance
+    pub fn from_words(high: u64, low: u64) -> Self {
+        LotBalance(((high as u128) << 64) | low as u128)
+    }
+
+    /// Split into the high and low words for storage
+    pub fn to_words(self) -> (u64, u64) {
+        ((self.0 >> 64) as u64, self.0 as u64)
+    }
+
+    /// Add `lots`, returning None on overflow
+    pub fn checked_add_lots(self, lots: Lots) -> Option<Self> {
+        self.checked_add(LotBalance::from(lots))
+    }
+
+    /// Subtract `lots`, returning None if the balance is insufficient
+    pub fn checked_sub_lots(self, lots: Lots) -> Option<Self> {
+        self.checked_sub(LotBalance::from(lots))
+    }
+
+    /// Narrow to lots. Returns None if the balance doesn't fit in u64.
+    pub fn to_lots(self) -> Option<Lots> {
+        u64::try_from(self.0).ok().map(Lots)
+    }
+}
+
+impl From<Lots> for LotBalance {
+    fn from(lots: Lots) -> Self {
+        LotBalance(lots.0 as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        let balance = LotBalance::from_words(1, 2);
+        assert_eq!(balance.0, (1 << 64) + 2);
+        assert_eq!(balance.to_words(), (1, 2));
+    }
+
+    #[test]
+    fn test_lots_above_u64() {
+        let balance = LotBalance::from(Lots(u64::MAX))
+            .checked_add_lots(Lots(1))
+            .unwrap();
+        assert_eq!(balance.to_words(), (1, 0));
+        assert_eq!(balance.to_lots(), None);
+
+        let balance = balance.checked_sub_lots(Lots(1)).unwrap();
+        assert_eq!(balance.to_lots(), Some(Lots(u64::MAX)));
+
+        assert_eq!(LotBalance(0).checked_sub_lots(Lots(1)), None);
+        assert_eq!(LotBalance(u128::MAX).checked_add_lots(Lots(1)), None);
+    }
+}
@@ -40,17 +40,16 @@ impl From<&Atoms> for Lots {
     /// * Swap bytes to convert to little endian
     /// swap_bytes([0x00, 0x00, ..., 0x01]) = [0x01, 0x00, ...] = 1
     ///
-    /// * We must divide by 10^6 to convert atoms to lots
+    /// * We must divide by 10^6 to convert atoms to lots. The division is exact, see
+    ///   [`Atoms::div_lot_size`].
     /// lots = (word_2 * 2^64 + word_3) / 10^6
     ///
+    /// * Results above u64::MAX lots are truncated to the lower 64 bits. Use
+    ///   [`Atoms::split_lots`] to reject them.
+    ///
     fn from(atoms: &Atoms) -> Self {
-        let high = atoms.0[2].swap_bytes();
-        let low = atoms.0[3].swap_bytes();
-
-        let high_lots = high.wrapping_mul(HIGH_LOTS_SCALE);
-        let low_lots = low / 1_000_000;
-
-        Lots(high_lots.wrapping_add(low_lots))
+        let (_, lots, _) = atoms.div_lot_size();
+        Lots(lots)
     }
 }
 
@@ -106,13 +105,24 @@ mod tests {
             Lots::from(&Atoms([0, 0, 1u64.swap_bytes(), 1_000_000u64.swap_bytes()])).0,
             HIGH_LOTS_SCALE + 1
         );
+
+        // 2^64 + 448_384 atoms are exactly HIGH_LOTS_SCALE + 1 lots
+        assert_eq!(
+            Lots::from(&Atoms([0, 0, 1u64.swap_bytes(), 448_384u64.swap_bytes()])).0,
+            HIGH_LOTS_SCALE + 1
+        );
+        assert_eq!(
+            Lots::from(&Atoms([0, 0, 1u64.swap_bytes(), 448_383u64.swap_bytes()])).0,
+            HIGH_LOTS_SCALE
+        );
     }
 
     #[test]
     fn test_overflow() {
+        // (2^128 - 1) / 10^6 truncated to 64 bits
         assert_eq!(
             Lots::from(&Atoms([0, 0, u64::MAX.swap_bytes(), u64::MAX.swap_bytes()])).0,
-            0
+            ((u128::MAX / 1_000_000) as u64)
         );
     }
 }
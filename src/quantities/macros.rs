@@ -6,6 +6,38 @@ macro_rules! define_custom_types {
             #[derive(Debug, Clone, Copy, PartialEq)]
             pub struct $type(pub $t);
 
+            impl $type {
+                /// Add `rhs`, returning None on overflow
+                pub fn checked_add(self, rhs: Self) -> Option<Self> {
+                    self.0.checked_add(rhs.0).map($type)
+                }
+
+                /// Subtract `rhs`, returning None on underflow
+                pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    self.0.checked_sub(rhs.0).map($type)
+                }
+            }
+
+            impl $crate::quantities::CheckedMul for $type {
+                type Output = Self;
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    self.0.checked_mul(rhs.0).map($type)
+                }
+            }
+
+            impl $crate::quantities::CheckedDiv for $type {
+                type Output = Self;
+
+                fn checked_div(self, rhs: Self) -> Option<Self> {
+                    self.0.checked_div(rhs.0).map($type)
+                }
+            }
+
+            // Operators wrap in release builds. The `checked-math` feature leaves them
+            // out so that all quantity arithmetic goes through the checked methods.
+
+            #[cfg(not(feature = "checked-math"))]
             impl core::ops::Add for $type {
                 type Output = Self;
 
@@ -14,12 +46,14 @@ macro_rules! define_custom_types {
                 }
             }
 
+            #[cfg(not(feature = "checked-math"))]
             impl core::ops::AddAssign for $type {
                 fn add_assign(&mut self, rhs: Self) {
                     self.0 += rhs.0;
                 }
             }
 
+            #[cfg(not(feature = "checked-math"))]
             impl core::ops::Sub for $type {
                 type Output = Self;
 
@@ -28,12 +62,14 @@ macro_rules! define_custom_types {
                 }
             }
 
+            #[cfg(not(feature = "checked-math"))]
             impl core::ops::SubAssign for $type {
                 fn sub_assign(&mut self, rhs: Self) {
                     self.0 -= rhs.0;
                 }
             }
 
+            #[cfg(not(feature = "checked-math"))]
             impl core::ops::Mul for $type {
                 type Output = Self;
 
@@ -42,6 +78,7 @@ macro_rules! define_custom_types {
                 }
             }
 
+            #[cfg(not(feature = "checked-math"))]
             impl core::ops::Div for $type {
                 type Output = Self;
 
@@ -57,6 +94,44 @@ macro_rules! define_custom_types {
 macro_rules! define_inter_type_operations {
     ($type_1:ident<$t1:ty>, $type_2:ident<$t2:ty>, $type_result:ident<$tr:ty>) => {
         // type_1 * type_2 = type_result
+        impl $crate::quantities::CheckedMul<$type_2> for $type_1 {
+            type Output = $type_result;
+
+            fn checked_mul(self, rhs: $type_2) -> Option<$type_result> {
+                (self.0 as $tr).checked_mul(rhs.0 as $tr).map($type_result)
+            }
+        }
+
+        // type_2 * type_1 = type_result
+        impl $crate::quantities::CheckedMul<$type_1> for $type_2 {
+            type Output = $type_result;
+
+            fn checked_mul(self, rhs: $type_1) -> Option<$type_result> {
+                (self.0 as $tr).checked_mul(rhs.0 as $tr).map($type_result)
+            }
+        }
+
+        // type_result / type_2 = type_1
+        impl $crate::quantities::CheckedDiv<$type_2> for $type_result {
+            type Output = $type_1;
+
+            fn checked_div(self, rhs: $type_2) -> Option<$type_1> {
+                let quotient = (self.0 as $tr).checked_div(rhs.0 as $tr)?;
+                <$t1>::try_from(quotient).ok().map($type_1)
+            }
+        }
+
+        // type_result / type_1 = type_2
+        impl $crate::quantities::CheckedDiv<$type_1> for $type_result {
+            type Output = $type_2;
+
+            fn checked_div(self, rhs: $type_1) -> Option<$type_2> {
+                let quotient = (self.0 as $tr).checked_div(rhs.0 as $tr)?;
+                <$t2>::try_from(quotient).ok().map($type_2)
+            }
+        }
+
+        #[cfg(not(feature = "checked-math"))]
         impl core::ops::Mul<$type_2> for $type_1 {
             type Output = $type_result;
 
@@ -65,7 +140,7 @@ macro_rules! define_inter_type_operations {
             }
         }
 
-        // type_2 * type_1 = type_result
+        #[cfg(not(feature = "checked-math"))]
         impl core::ops::Mul<$type_1> for $type_2 {
             type Output = $type_result;
 
@@ -74,7 +149,7 @@ macro_rules! define_inter_type_operations {
             }
         }
 
-        // type_result / type_2 = type_1
+        #[cfg(not(feature = "checked-math"))]
         impl core::ops::Div<$type_2> for $type_result {
             type Output = $type_1;
 
@@ -83,7 +158,7 @@ macro_rules! define_inter_type_operations {
             }
         }
 
-        // type_result / type_1 = type_2
+        #[cfg(not(feature = "checked-math"))]
         impl core::ops::Div<$type_1> for $type_result {
             type Output = $type_2;
 
@@ -93,3 +168,27 @@ macro_rules! define_inter_type_operations {
         }
     };
 }
+
+#[macro_export]
+macro_rules! define_lot_conversions {
+    ($lots:ident, $atoms_per_lot:ident, $atoms:ident) => {
+        impl $lots {
+            /// Convert lots to atoms. Returns None on overflow.
+            pub fn checked_to_atoms(self, atoms_per_lot: $atoms_per_lot) -> Option<$atoms> {
+                self.0.checked_mul(atoms_per_lot.0).map($atoms)
+            }
+        }
+
+        impl $atoms {
+            /// Convert atoms to lots, rounding dust in the given direction. Returns None
+            /// on division by zero or overflow.
+            pub fn checked_to_lots(
+                self,
+                atoms_per_lot: $atoms_per_lot,
+                rounding: $crate::quantities::Rounding,
+            ) -> Option<$lots> {
+                $crate::quantities::mul_div(self.0, 1, atoms_per_lot.0, rounding).map($lots)
+            }
+        }
+    };
+}
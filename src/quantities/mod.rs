@@ -1,8 +1,14 @@
 pub mod atoms;
+pub mod checked;
+pub mod conversions;
+pub mod lot_balance;
 pub mod lots;
 mod macros;
 pub mod quantities;
 
 pub use atoms::*;
+pub use checked::*;
+pub use conversions::*;
+pub use lot_balance::*;
 pub use lots::*;
 pub use quantities::*;
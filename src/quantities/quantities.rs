@@ -56,7 +56,10 @@ define_inter_type_operations!(
 mod tests {
     use super::*;
 
+    use crate::quantities::{CheckedDiv, CheckedMul};
+
     #[test]
+    #[cfg(not(feature = "checked-math"))]
     fn test_quote_operations() {
         let lots = QuoteLots(5);
         let atoms_per_lot = QuoteAtomsPerQuoteLot(10);
@@ -72,6 +75,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "checked-math"))]
     fn test_mixed_type_operations() {
         let lots_per_tick = QuoteLotsPerBaseUnitPerTick(100);
         let ticks = Ticks(5);
@@ -87,6 +91,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "checked-math"))]
     fn test_large_numbers() {
         let lots_per_tick = QuoteLotsPerBaseUnitPerTick(1_000_000);
         let ticks = Ticks(1_000);
@@ -94,4 +99,72 @@ mod tests {
         // Should handle larger numbers without overflow since result type is u64
         assert_eq!(lots_per_tick * ticks, QuoteLotsBaseUnit(1_000_000_000));
     }
+
+    #[test]
+    fn test_checked_operations_at_boundaries() {
+        assert_eq!(QuoteLots(u64::MAX).checked_add(QuoteLots(1)), None);
+        assert_eq!(
+            QuoteLots(u64::MAX - 1).checked_add(QuoteLots(1)),
+            Some(QuoteLots(u64::MAX))
+        );
+
+        assert_eq!(BaseLots(0).checked_sub(BaseLots(1)), None);
+        assert_eq!(BaseLots(1).checked_sub(BaseLots(1)), Some(BaseLots(0)));
+
+        assert_eq!(Ticks(u32::MAX).checked_mul(Ticks(2)), None);
+        assert_eq!(Ticks(u32::MAX).checked_mul(Ticks(1)), Some(Ticks(u32::MAX)));
+
+        assert_eq!(QuoteAtoms(1).checked_div(QuoteAtoms(0)), None);
+        assert_eq!(
+            QuoteAtoms(u64::MAX).checked_div(QuoteAtoms(1)),
+            Some(QuoteAtoms(u64::MAX))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "checked-math"))]
+    fn test_max_ticks() {
+        // Ticks widen to u64 before multiplying
+        let lots_per_tick = QuoteLotsPerBaseUnitPerTick(1 << 32);
+        assert_eq!(
+            lots_per_tick * Ticks(u32::MAX),
+            QuoteLotsBaseUnit(u64::MAX - (1 << 32) + 1)
+        );
+        assert_eq!(
+            QuoteLotsBaseUnit(u64::MAX - (1 << 32) + 1) / lots_per_tick,
+            Ticks(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_checked_inter_type_operations() {
+        assert_eq!(
+            BaseLots(5).checked_mul(BaseAtomsPerBaseLot(10)),
+            Some(BaseAtoms(50))
+        );
+        assert_eq!(
+            BaseAtomsPerBaseLot(10).checked_mul(BaseLots(5)),
+            Some(BaseAtoms(50))
+        );
+        assert_eq!(BaseLots(u64::MAX).checked_mul(BaseAtomsPerBaseLot(2)), None);
+
+        assert_eq!(
+            QuoteAtoms(50).checked_div(QuoteLots(5)),
+            Some(QuoteAtomsPerQuoteLot(10))
+        );
+        assert_eq!(QuoteAtoms(50).checked_div(QuoteAtomsPerQuoteLot(0)), None);
+
+        // Ticks widen to u64 before multiplying
+        let lots_per_tick = QuoteLotsPerBaseUnitPerTick(1 << 32);
+        assert_eq!(
+            lots_per_tick.checked_mul(Ticks(u32::MAX)),
+            Some(QuoteLotsBaseUnit(u64::MAX - (1 << 32) + 1))
+        );
+
+        // The quotient doesn't fit in u32 ticks
+        assert_eq!(
+            QuoteLotsBaseUnit(u64::MAX).checked_div(QuoteLotsPerBaseUnitPerTick(1)),
+            None
+        );
+    }
 }
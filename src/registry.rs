@@ -0,0 +1,68 @@
+//! Owner managed lists of addresses
+//!
+//! Entries live in storage and are set by [`crate::OWNER`] with
+//! [`crate::handler::handle_13_set_registry_entry()`], so a deployed contract is
+//! configured without rebuilding it. Every build, including tests, reads the same lists.
+//!
+use core::mem::MaybeUninit;
+
+use crate::{
+    state::{RegistryEntry, RegistryKey, SlotState},
+    types::Address,
+};
+
+/// Operators allowed to credit balances when built with `operator-allowlist`, e.g. the
+/// paired market contract
+pub const OPERATOR: u8 = 0;
+
+/// ERC20 tokens taking a fee on transfer. Credits of these tokens measure the received
+/// balance instead of trusting the requested amount.
+pub const FEE_ON_TRANSFER_TOKEN: u8 = 1;
+
+/// Market contracts allowed to lock, unlock and settle funds held by the vault
+pub const MARKET: u8 = 2;
+
+/// Number of lists. Entries for other lists are rejected.
+pub const NUM_LISTS: u8 = 3;
+
+/// Whether `account` is registered in `list`
+pub fn is_registered(account: &Address, list: u8) -> bool {
+    let mut entry_maybe = MaybeUninit::<RegistryEntry>::uninit();
+    let entry = unsafe {
+        RegistryEntry::load(
+            &RegistryKey {
+                account: *account,
+                list,
+            },
+            &mut entry_maybe,
+        )
+    };
+
+    entry.registered != 0
+}
+
+/// Register `account` in `list` without going through the owner handler
+#[cfg(test)]
+pub fn register(account: &Address, list: u8) {
+    let key = &RegistryKey {
+        account: *account,
+        list,
+    };
+
+    let mut entry_maybe = MaybeUninit::<RegistryEntry>::uninit();
+    unsafe {
+        let entry = RegistryEntry::load(key, &mut entry_maybe);
+        entry.registered = 1;
+        entry.store(key);
+        crate::flush_storage();
+    }
+}
+
+/// Call as `operator` and register it in [`OPERATOR`], so that credits pass the
+/// `operator-allowlist` check
+#[cfg(test)]
+pub fn set_operator_sender(operator: &Address) {
+    crate::test_fixtures::set_sender(operator);
+
+    register(operator, OPERATOR);
+}
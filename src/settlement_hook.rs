@@ -0,0 +1,121 @@
+//! Settlement hooks route withdrawals into an external vault.
+//!
+//! A trader registers the hook contract as its default recipient with the settlement hook
+//! flag set (see [`crate::state::TraderConfig`]). Withdrawals to the hook transfer the tokens
+//! and then call `onGoblinSettle(address token, uint256 amount, bytes data)` on it, where
+//! `data` holds the ABI encoded trader.
+//!
+//! * The hook must return the `onGoblinSettle` selector, else the withdrawal fails. Addresses
+//!   without code return nothing, so a wallet registered by mistake can't be used as a hook.
+//!
+//! * The reentrancy lock is held during the call, so the hook can't call back into handlers.
+//!
+use core::mem::MaybeUninit;
+
+use crate::{call_contract, quantities::Atoms, read_return_data, types::Address};
+
+// keccak256('onGoblinSettle(address,uint256,bytes)') = 0x8edd3760
+pub const ON_GOBLIN_SETTLE_SELECTOR: [u8; 4] = [0x8e, 0xdd, 0x37, 0x60];
+
+/// Notify `hook` that `amount` of `token` withdrawn by `trader` was transferred to it.
+///
+/// Returns 0 on success and 1 if the call reverted or didn't return the selector.
+pub fn on_goblin_settle(hook: &Address, trader: &Address, token: &Address, amount: &Atoms) -> u8 {
+    let mut calldata = [0u8; 4 + 32 * 5];
+
+    calldata[0..4].copy_from_slice(&ON_GOBLIN_SETTLE_SELECTOR);
+
+    // 4..36: token address
+    // 4..16 are zeroes, 16..36 holds 20 byte address
+    calldata[16..36].copy_from_slice(token);
+
+    // 36..68: amount transferred
+    // This is a 32 byte value
+    let amount_as_be_bytes: &[u8; 32] = unsafe { &*(amount.0.as_ptr() as *const [u8; 32]) };
+    calldata[36..68].copy_from_slice(amount_as_be_bytes);
+
+    // 68..100: offset of `data`, i.e. 3 words
+    calldata[99] = 0x60;
+
+    // 100..132: length of `data`
+    calldata[131] = 32;
+
+    // 132..164: `data`, the trader address
+    // 132..144 are zeroes, 144..164 holds 20 byte address
+    calldata[144..164].copy_from_slice(trader);
+
+    let value = Atoms::default();
+    let return_data_len: &mut usize = &mut 0;
+
+    let call_result = unsafe {
+        call_contract(
+            hook.as_ptr(),
+            calldata.as_ptr(),
+            calldata.len(),
+            value.0.as_ptr() as *const u8, // Zero value
+            200_000, // 200k gas. We need to explicitly specify gas else, tx fails
+            return_data_len,
+        )
+    };
+
+    if call_result != 0 || *return_data_len < 32 {
+        return 1;
+    }
+
+    // bytes4 is left aligned in the return word
+    let mut selector_maybe = MaybeUninit::<[u8; 4]>::uninit();
+    let selector = unsafe {
+        read_return_data(selector_maybe.as_mut_ptr() as *mut u8, 0, 4);
+        selector_maybe.assume_init_ref()
+    };
+
+    (*selector != ON_GOBLIN_SETTLE_SELECTOR) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use tiny_keccak::{Hasher, Keccak};
+
+    use super::*;
+    use crate::{get_last_call, set_return_data};
+
+    const HOOK: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+    const TOKEN: Address = hex!("7E32b54800705876d3b5cFbc7d9c226a211F7C1a");
+
+    #[test]
+    fn test_selector_matches_signature() {
+        let mut hasher = Keccak::v256();
+        hasher.update(b"onGoblinSettle(address,uint256,bytes)");
+        let mut hash = [0u8; 32];
+        hasher.finalize(&mut hash);
+
+        assert_eq!(ON_GOBLIN_SETTLE_SELECTOR, hash[..4]);
+    }
+
+    #[test]
+    fn test_on_goblin_settle() {
+        let amount = Atoms([0, 0, 0, 1_000_000u64.swap_bytes()]);
+
+        let mut return_data = vec![0u8; 32];
+        return_data[..4].copy_from_slice(&ON_GOBLIN_SETTLE_SELECTOR);
+        set_return_data(return_data);
+        assert_eq!(on_goblin_settle(&HOOK, &TRADER, &TOKEN, &amount), 0);
+
+        let (contract, calldata) = get_last_call();
+        assert_eq!(contract, HOOK);
+        assert_eq!(calldata[..4], ON_GOBLIN_SETTLE_SELECTOR);
+        assert_eq!(calldata[16..36], TOKEN);
+        assert_eq!(calldata[60..68], 1_000_000u64.to_be_bytes());
+        assert_eq!(calldata[144..164], TRADER);
+
+        // Wallets return no data
+        set_return_data(vec![]);
+        assert_eq!(on_goblin_settle(&HOOK, &TRADER, &TOKEN, &amount), 1);
+
+        // Wrong selector
+        set_return_data(vec![1u8; 32]);
+        assert_eq!(on_goblin_settle(&HOOK, &TRADER, &TOKEN, &amount), 1);
+    }
+}
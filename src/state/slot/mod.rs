@@ -1,3 +1,7 @@
+pub mod registry_entry;
+pub mod trader_config;
 pub mod trader_token_state;
 
+pub use registry_entry::*;
+pub use trader_config::*;
 pub use trader_token_state::*;
@@ -0,0 +1,70 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    native_keccak256,
+    state::{slot_key::SlotKey, SlotState},
+    storage_cache_bytes32, storage_load_bytes32,
+    types::Address,
+};
+
+#[repr(C)]
+pub struct RegistryKey {
+    pub account: Address,
+
+    /// The list `account` is registered in, see [`crate::registry`]
+    pub list: u8,
+}
+
+impl SlotKey for RegistryKey {
+    fn discriminator() -> u8 {
+        2
+    }
+
+    fn to_keccak256(&self) -> [u8; 32] {
+        let mut key = [0u8; 32];
+
+        let bytes = {
+            let mut b = [0u8; core::mem::size_of::<Self>() + 1];
+            b[0] = Self::discriminator();
+            b[1..21].copy_from_slice(&self.account);
+            b[21] = self.list;
+            b
+        };
+
+        unsafe {
+            native_keccak256(
+                bytes.as_ptr(),
+                core::mem::size_of::<Self>() + 1,
+                key.as_mut_ptr(),
+            );
+        }
+
+        key
+    }
+}
+
+/// Whether an account is in a registry list
+#[repr(C)]
+#[derive(Debug)]
+pub struct RegistryEntry {
+    /// Nonzero if the account is registered
+    pub registered: u8,
+    _padding: [u8; 31],
+}
+
+impl SlotState<RegistryKey, RegistryEntry> for RegistryEntry {
+    unsafe fn load<'a>(
+        key: &RegistryKey,
+        slot: &'a mut MaybeUninit<RegistryEntry>,
+    ) -> &'a mut RegistryEntry {
+        storage_load_bytes32(key.to_keccak256().as_ptr(), slot.as_mut_ptr() as *mut u8);
+        slot.assume_init_mut()
+    }
+
+    unsafe fn store(&self, key: &RegistryKey) {
+        storage_cache_bytes32(
+            key.to_keccak256().as_ptr(),
+            self as *const RegistryEntry as *const u8,
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use core::mem::MaybeUninit;
+
+use crate::{
+    native_keccak256,
+    state::{slot_key::SlotKey, SlotState},
+    storage_cache_bytes32, storage_load_bytes32,
+    types::Address,
+};
+
+/// Address used by withdrawal calls to request the default recipient
+pub const DEFAULT_RECIPIENT: Address = [0u8; 20];
+
+#[repr(C)]
+pub struct TraderConfigKey {
+    pub trader: Address,
+}
+
+impl SlotKey for TraderConfigKey {
+    fn discriminator() -> u8 {
+        1
+    }
+
+    fn to_keccak256(&self) -> [u8; 32] {
+        let mut key = [0u8; 32];
+
+        let bytes = {
+            let mut b = [0u8; core::mem::size_of::<Self>() + 1];
+            b[0] = Self::discriminator();
+            b[1..21].copy_from_slice(&self.trader);
+            b
+        };
+
+        unsafe {
+            native_keccak256(
+                bytes.as_ptr(),
+                core::mem::size_of::<Self>() + 1,
+                key.as_mut_ptr(),
+            );
+        }
+
+        key
+    }
+}
+
+/// Per-trader settings
+#[repr(C)]
+#[derive(Debug)]
+pub struct TraderConfig {
+    /// Withdrawals to [`DEFAULT_RECIPIENT`] are sent here. Unset (zero) means the trader.
+    pub default_recipient: Address,
+
+    /// Nonzero if `default_recipient` is a settlement hook. Withdrawals to it are followed
+    /// by a call to `onGoblinSettle`, see [`crate::settlement_hook`].
+    pub settlement_hook: u8,
+
+    /// Nonzero if credits to the trader are only accepted from accounts in the
+    /// [`crate::registry::OPERATOR`] list, regardless of the `operator-allowlist` feature
+    pub operator_allowlist: u8,
+    _padding: [u8; 10],
+}
+
+impl TraderConfig {
+    /// Resolve the recipient of a withdrawal by `trader`. The zero address is replaced
+    /// by the configured default recipient, or by the trader if none is set.
+    pub fn resolve_recipient(&self, trader: &Address, recipient: &Address) -> Address {
+        if *recipient != DEFAULT_RECIPIENT {
+            *recipient
+        } else if self.default_recipient != DEFAULT_RECIPIENT {
+            self.default_recipient
+        } else {
+            *trader
+        }
+    }
+
+    /// Whether a withdrawal to `recipient` must notify it as a settlement hook
+    pub fn is_settlement_hook(&self, recipient: &Address) -> bool {
+        self.settlement_hook != 0 && *recipient == self.default_recipient
+    }
+}
+
+impl SlotState<TraderConfigKey, TraderConfig> for TraderConfig {
+    unsafe fn load<'a>(
+        key: &TraderConfigKey,
+        slot: &'a mut MaybeUninit<TraderConfig>,
+    ) -> &'a mut TraderConfig {
+        storage_load_bytes32(key.to_keccak256().as_ptr(), slot.as_mut_ptr() as *mut u8);
+        slot.assume_init_mut()
+    }
+
+    unsafe fn store(&self, key: &TraderConfigKey) {
+        storage_cache_bytes32(
+            key.to_keccak256().as_ptr(),
+            self as *const TraderConfig as *const u8,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use super::*;
+
+    const TRADER: Address = hex!("3f1Eae7D46d88F08fc2F8ed27FCb2AB183EB2d0E");
+    const WALLET: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+
+    #[test]
+    fn test_resolve_recipient() {
+        let mut config = TraderConfig {
+            default_recipient: DEFAULT_RECIPIENT,
+            settlement_hook: 0,
+            operator_allowlist: 0,
+            _padding: [0u8; 10],
+        };
+
+        assert_eq!(
+            config.resolve_recipient(&TRADER, &DEFAULT_RECIPIENT),
+            TRADER
+        );
+        assert_eq!(config.resolve_recipient(&TRADER, &WALLET), WALLET);
+
+        config.default_recipient = WALLET;
+        assert_eq!(
+            config.resolve_recipient(&TRADER, &DEFAULT_RECIPIENT),
+            WALLET
+        );
+        assert_eq!(config.resolve_recipient(&TRADER, &TRADER), TRADER);
+    }
+
+    #[test]
+    fn test_is_settlement_hook() {
+        let mut config = TraderConfig {
+            default_recipient: WALLET,
+            settlement_hook: 0,
+            operator_allowlist: 0,
+            _padding: [0u8; 10],
+        };
+        assert!(!config.is_settlement_hook(&WALLET));
+
+        config.settlement_hook = 1;
+        assert!(config.is_settlement_hook(&WALLET));
+        assert!(!config.is_settlement_hook(&TRADER));
+    }
+}
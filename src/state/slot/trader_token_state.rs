@@ -2,7 +2,7 @@ use core::mem::MaybeUninit;
 
 use crate::{
     native_keccak256,
-    quantities::Lots,
+    quantities::{LotBalance, Lots},
     state::{slot_key::SlotKey, SlotState},
     storage_cache_bytes32, storage_load_bytes32,
     types::Address,
@@ -42,12 +42,44 @@ impl SlotKey for TraderTokenKey {
     }
 }
 
+/// Locked and free lot balances of a trader for one token
+///
+/// * Layout v2 stores u128 balances as little endian u64 words. The high words take
+///   the place of the v1 padding, which was always zero, so v1 slots read as the same
+///   balances without a migration.
+///
+/// * `lots_locked` and `lots_free` are the low words. Handlers must go through
+///   [`TraderTokenState::free_balance`] and [`TraderTokenState::locked_balance`].
+///
 #[repr(C)]
 #[derive(Debug)]
 pub struct TraderTokenState {
     pub lots_locked: Lots,
     pub lots_free: Lots,
-    _padding: [u8; 16],
+    pub lots_locked_high: u64,
+    pub lots_free_high: u64,
+}
+
+impl TraderTokenState {
+    pub fn locked_balance(&self) -> LotBalance {
+        LotBalance::from_words(self.lots_locked_high, self.lots_locked.0)
+    }
+
+    pub fn set_locked_balance(&mut self, balance: LotBalance) {
+        let (high, low) = balance.to_words();
+        self.lots_locked_high = high;
+        self.lots_locked = Lots(low);
+    }
+
+    pub fn free_balance(&self) -> LotBalance {
+        LotBalance::from_words(self.lots_free_high, self.lots_free.0)
+    }
+
+    pub fn set_free_balance(&mut self, balance: LotBalance) {
+        let (high, low) = balance.to_words();
+        self.lots_free_high = high;
+        self.lots_free = Lots(low);
+    }
 }
 
 impl SlotState<TraderTokenKey, TraderTokenState> for TraderTokenState {
@@ -0,0 +1,79 @@
+//! Setup shared by handler and getter tests
+//!
+//! Hostio state lives in [`crate::hostio`]'s test hooks. These helpers build on them to
+//! seed trader balances and encode token call results.
+//!
+use core::mem::MaybeUninit;
+
+use crate::{
+    getter::read_trader_token_state,
+    hostio::*,
+    quantities::LotBalance,
+    state::{SlotState, TraderTokenKey, TraderTokenState},
+    types::Address,
+};
+
+/// Encode `value` as a big endian uint256, e.g. a `balanceOf()` result
+pub fn encode_u256(value: u64) -> Vec<u8> {
+    let mut word = vec![0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Make every token call return true until the return data is replaced
+pub fn set_token_calls_succeed() {
+    set_return_data(encode_u256(1));
+}
+
+/// Set `msg_sender` to `sender`, left padded to a word
+pub fn set_sender(sender: &Address) {
+    let mut msg_sender = [0u8; 32];
+    msg_sender[12..].copy_from_slice(sender);
+    set_msg_sender(msg_sender);
+}
+
+/// Make `trader` the sender with `lots` free in `token`, and let token calls succeed
+pub fn set_up_trader_with_lots(trader: &Address, token: &Address, lots: u64) {
+    set_sender(trader);
+    set_token_calls_succeed();
+    set_free_balance(trader, token, LotBalance(lots as u128));
+}
+
+/// Overwrite the free balance of `trader` in `token` and flush it
+pub fn set_free_balance(trader: &Address, token: &Address, balance: LotBalance) {
+    update_trader_token_state(trader, token, |state| state.set_free_balance(balance));
+}
+
+/// Overwrite the locked balance of `trader` in `token` and flush it
+pub fn set_locked_balance(trader: &Address, token: &Address, balance: LotBalance) {
+    update_trader_token_state(trader, token, |state| state.set_locked_balance(balance));
+}
+
+fn update_trader_token_state(
+    trader: &Address,
+    token: &Address,
+    update: impl FnOnce(&mut TraderTokenState),
+) {
+    let key = &TraderTokenKey {
+        trader: *trader,
+        token: *token,
+    };
+    let mut trader_token_state_maybe = MaybeUninit::<TraderTokenState>::uninit();
+    unsafe {
+        let trader_token_state = TraderTokenState::load(key, &mut trader_token_state_maybe);
+        update(trader_token_state);
+        trader_token_state.store(key);
+        storage_flush_cache(true);
+    }
+}
+
+/// Read the free balance of `trader` in `token` through the getter
+pub fn read_free_balance(trader: &Address, token: &Address) -> LotBalance {
+    let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+        trader: *trader,
+        token: *token,
+    });
+    let trader_token_state: &TraderTokenState =
+        unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+    trader_token_state.free_balance()
+}
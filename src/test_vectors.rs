@@ -0,0 +1,243 @@
+//! Wire format test vectors
+//!
+//! `test-vectors/goblin-core.json` holds a call to every selector so that SDKs in other
+//! languages can check their encoders against this crate. It is written by
+//! `examples/generate-test-vectors.rs` from the exported selector table:
+//!
+//! ```sh
+//! cargo run --example generate-test-vectors
+//! ```
+//!
+//! Every vector is run through [`crate::user_entrypoint`] here and its effect on storage,
+//! calls and return data is checked.
+//!
+use core::mem::MaybeUninit;
+
+use hex_literal::hex;
+use serde_json::Value;
+
+use crate::{
+    getter::{read_trader_token_state, MANIFEST},
+    hostio::*,
+    interface::{FieldKind, SELECTORS},
+    quantities::LotBalance,
+    registry::{self, MARKET, OPERATOR},
+    state::{SlotState, TraderConfig, TraderConfigKey, TraderTokenKey, TraderTokenState},
+    test_fixtures::{
+        encode_u256, read_free_balance, set_free_balance, set_locked_balance, set_sender,
+        set_token_calls_succeed,
+    },
+    types::{Address, NATIVE_TOKEN},
+    user_entrypoint,
+};
+
+const VECTORS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test-vectors/goblin-core.json");
+
+const TOKEN: Address = hex!("7e32b54800705876d3b5cfbc7d9c226a211f7c1a");
+const TRADER: Address = hex!("3f1eae7d46d88f08fc2f8ed27fcb2ab183eb2d0e");
+const RECIPIENT: Address = hex!("84401cd7abbebb22acb7af2becfd9be56c30bcf1");
+
+/// Lots TRADER holds free and locked in ETH and TOKEN before each vector
+const INITIAL_LOTS: u128 = 10;
+
+fn read_vectors() -> Vec<Value> {
+    let json = std::fs::read_to_string(VECTORS_PATH).unwrap();
+    serde_json::from_str::<Value>(&json)
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+fn calldata(vector: &Value) -> Vec<u8> {
+    let calldata = vector["calldata"].as_str().unwrap();
+    hex::decode(calldata.strip_prefix("0x").unwrap()).unwrap()
+}
+
+fn locked_balance(trader: &Address, token: &Address) -> LotBalance {
+    let trader_token_state_bytes = read_trader_token_state(&TraderTokenKey {
+        trader: *trader,
+        token: *token,
+    });
+    let trader_token_state: &TraderTokenState =
+        unsafe { &*(trader_token_state_bytes.as_ptr() as *const TraderTokenState) };
+    trader_token_state.locked_balance()
+}
+
+/// TRADER, the owner, is the sender and a registered operator and market. It holds
+/// [`INITIAL_LOTS`] free and locked lots in ETH and TOKEN, sends 1 lot of ETH and token
+/// calls succeed.
+fn set_up() {
+    clear_state();
+
+    set_sender(&TRADER);
+    registry::register(&TRADER, OPERATOR);
+    registry::register(&TRADER, MARKET);
+    for token in [NATIVE_TOKEN, TOKEN] {
+        set_free_balance(&TRADER, &token, LotBalance(INITIAL_LOTS));
+        set_locked_balance(&TRADER, &token, LotBalance(INITIAL_LOTS));
+    }
+
+    // 10^6 wei = 1 lot
+    set_msg_value(hex!(
+        "00000000000000000000000000000000000000000000000000000000000F4240"
+    ));
+    set_token_calls_succeed();
+}
+
+/// Check the effect of the vector called `name`. The return data must be checked first
+/// since the balance getters overwrite it.
+fn check(name: &str) {
+    let free = |trader: &Address, token: &Address| read_free_balance(trader, token).0;
+    let locked = |trader: &Address, token: &Address| locked_balance(trader, token).0;
+
+    match name {
+        "credit_eth" => {
+            assert_eq!(free(&TRADER, &NATIVE_TOKEN), INITIAL_LOTS + 1);
+            assert_eq!(get_logs().len(), 1);
+        }
+        "credit_erc20" => {
+            assert_eq!(get_last_call().0, TOKEN);
+            assert_eq!(free(&TRADER, &TOKEN), INITIAL_LOTS + 1);
+        }
+        "withdraw_eth" => {
+            assert_eq!(get_last_call().0, RECIPIENT);
+            assert_eq!(free(&TRADER, &NATIVE_TOKEN), INITIAL_LOTS - 2);
+        }
+        "withdraw_erc20_all" => {
+            let (contract, calldata) = get_last_call();
+            assert_eq!(contract, TOKEN);
+            assert_eq!(calldata[16..36], RECIPIENT);
+            assert_eq!(free(&TRADER, &TOKEN), 0);
+        }
+        "withdraw_erc20_all_minus" => {
+            // The zero address resolves to the trader
+            assert_eq!(get_last_call().1[16..36], TRADER);
+            assert_eq!(free(&TRADER, &TOKEN), 3);
+        }
+        "lock_funds" => {
+            assert_eq!(free(&TRADER, &TOKEN), INITIAL_LOTS - 5);
+            assert_eq!(locked(&TRADER, &TOKEN), INITIAL_LOTS + 5);
+        }
+        "unlock_funds" => {
+            assert_eq!(free(&TRADER, &TOKEN), INITIAL_LOTS + 5);
+            assert_eq!(locked(&TRADER, &TOKEN), INITIAL_LOTS - 5);
+        }
+        "settle_funds" => {
+            assert_eq!(locked(&TRADER, &TOKEN), INITIAL_LOTS - 5);
+            assert_eq!(free(&RECIPIENT, &TOKEN), 5);
+        }
+        "set_trader_config" => {
+            let mut trader_config_maybe = MaybeUninit::<TraderConfig>::uninit();
+            let trader_config = unsafe {
+                TraderConfig::load(
+                    &TraderConfigKey { trader: TRADER },
+                    &mut trader_config_maybe,
+                )
+            };
+            assert_eq!(trader_config.default_recipient, RECIPIENT);
+            assert_eq!(trader_config.settlement_hook, 1);
+            assert_eq!(trader_config.operator_allowlist, 1);
+        }
+        "credit_erc20_atoms" => {
+            // 2.5 lots pull 2 lots and return the dust
+            assert_eq!(get_test_result(), encode_u256(500_000));
+            assert_eq!(free(&TRADER, &TOKEN), INITIAL_LOTS + 2);
+        }
+        "withdraw_erc20_atoms" => {
+            assert_eq!(get_test_result(), encode_u256(999_999));
+            assert_eq!(get_last_call().1[16..36], RECIPIENT);
+            assert_eq!(free(&TRADER, &TOKEN), INITIAL_LOTS - 1);
+        }
+        "get_trader_token_state" => {
+            let result = get_test_result();
+            let trader_token_state: &TraderTokenState =
+                unsafe { &*(result.as_ptr() as *const TraderTokenState) };
+            assert_eq!(trader_token_state.free_balance().0, INITIAL_LOTS);
+            assert_eq!(trader_token_state.locked_balance().0, INITIAL_LOTS);
+        }
+        "get_interface_version" => {
+            assert_eq!(get_test_result(), MANIFEST);
+        }
+        "get_token_preflight" => {
+            // Balance and allowance as returned by the token, then the credited lots
+            let result = get_test_result();
+            assert_eq!(result[..32], encode_u256(1));
+            assert_eq!(result[32..64], encode_u256(1));
+            let trader_token_state: &TraderTokenState =
+                unsafe { &*(result[64..].as_ptr() as *const TraderTokenState) };
+            assert_eq!(trader_token_state.free_balance().0, INITIAL_LOTS);
+        }
+        "set_registry_entry" => {
+            assert!(registry::is_registered(&RECIPIENT, OPERATOR));
+        }
+        "batch_credit_and_withdraw" => {
+            assert_eq!(get_last_call().0, RECIPIENT);
+            assert_eq!(free(&TRADER, &NATIVE_TOKEN), INITIAL_LOTS);
+            assert_eq!(get_logs().len(), 2);
+        }
+        _ => panic!("no check for test vector {}", name),
+    }
+}
+
+#[test]
+fn test_vectors_match_selector_table() {
+    let mut covered = [false; SELECTORS.len()];
+
+    for vector in read_vectors() {
+        let name = vector["name"].as_str().unwrap();
+        let calls = vector["calls"].as_array().unwrap();
+        let calldata = calldata(&vector);
+        assert_eq!(calldata[0] as usize, calls.len(), "{}", name);
+
+        // Calls are read back with the payload lengths and field names of the table
+        let mut offset = 1;
+        for call in calls {
+            let selector = calldata[offset] as usize;
+            assert_eq!(call["selector"], selector, "{}", name);
+            let entry = &SELECTORS[selector];
+            covered[selector] = true;
+
+            let payload_len = u16::from_le_bytes([calldata[offset + 1], calldata[offset + 2]]);
+            assert_eq!(
+                payload_len as usize, entry.payload_len,
+                "{}: test vectors are stale, see src/test_vectors.rs",
+                name
+            );
+            offset += 3 + entry.payload_len;
+
+            let mut inputs: Vec<&str> = call["inputs"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            let mut fields: Vec<&str> = entry
+                .payload
+                .iter()
+                .filter(|field| field.kind != FieldKind::Padding)
+                .map(|field| field.name)
+                .collect();
+            inputs.sort();
+            fields.sort();
+            assert_eq!(inputs, fields, "{}", name);
+        }
+        assert_eq!(offset, calldata.len(), "{}", name);
+    }
+
+    assert!(covered.iter().all(|covered| *covered));
+}
+
+#[test]
+fn test_vectors_dispatch() {
+    for vector in read_vectors() {
+        let name = vector["name"].as_str().unwrap();
+        set_up();
+
+        let calldata = calldata(&vector);
+        set_test_args(calldata.clone());
+        assert_eq!(user_entrypoint(calldata.len()), 0, "{}", name);
+
+        check(name);
+    }
+}
@@ -0,0 +1,114 @@
+//! Withdrawal amounts
+//!
+//! Withdraw handlers take the requested lots as a u64 with two special encodings.
+//!
+//! * [`WITHDRAW_ALL`] (u64::MAX) withdraws the whole free balance.
+//!
+//! * Setting [`ALL_MINUS_FLAG`] (the most significant bit) withdraws the free balance
+//!   minus the lots in the lower 63 bits. Traders can keep a buffer, e.g. of the token
+//!   used to pay for gas.
+//!
+//! Other values withdraw exactly that many lots. Exact amounts are thus limited to
+//! 2^63 - 1 lots.
+//!
+//! A withdrawal moves at most u64::MAX lots. Withdrawing all or all minus a buffer fails
+//! if the resolved amount is larger, split the withdrawal into exact amounts instead.
+//!
+use crate::quantities::{LotBalance, Lots};
+
+/// Withdraw the whole free balance
+pub const WITHDRAW_ALL: Lots = Lots(u64::MAX);
+
+/// Withdraw the free balance minus the lots in the lower 63 bits
+pub const ALL_MINUS_FLAG: u64 = 1 << 63;
+
+/// Resolve requested lots against the free balance. Returns None if the free balance
+/// is insufficient or the resolved lots don't fit in u64.
+///
+/// Withdrawing all of an empty balance resolves to zero lots.
+pub fn resolve_withdraw_lots(free_balance: LotBalance, requested: Lots) -> Option<Lots> {
+    if requested == WITHDRAW_ALL {
+        return free_balance.to_lots();
+    }
+
+    if requested.0 & ALL_MINUS_FLAG != 0 {
+        let buffer = Lots(requested.0 & !ALL_MINUS_FLAG);
+        return free_balance.checked_sub_lots(buffer)?.to_lots();
+    }
+
+    free_balance.checked_sub_lots(requested)?;
+    Some(requested)
+}
+
+/// Whether `lots` is an exact amount, i.e. not one of the special encodings
+pub fn is_exact(lots: Lots) -> bool {
+    lots.0 & ALL_MINUS_FLAG == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_amount() {
+        assert_eq!(resolve_withdraw_lots(LotBalance(5), Lots(2)), Some(Lots(2)));
+        assert_eq!(resolve_withdraw_lots(LotBalance(5), Lots(5)), Some(Lots(5)));
+        assert_eq!(resolve_withdraw_lots(LotBalance(5), Lots(6)), None);
+    }
+
+    #[test]
+    fn test_withdraw_all() {
+        assert_eq!(
+            resolve_withdraw_lots(LotBalance(5), WITHDRAW_ALL),
+            Some(Lots(5))
+        );
+        assert_eq!(
+            resolve_withdraw_lots(LotBalance(0), WITHDRAW_ALL),
+            Some(Lots(0))
+        );
+    }
+
+    #[test]
+    fn test_withdraw_all_minus() {
+        assert_eq!(
+            resolve_withdraw_lots(LotBalance(5), Lots(ALL_MINUS_FLAG | 2)),
+            Some(Lots(3))
+        );
+        assert_eq!(
+            resolve_withdraw_lots(LotBalance(5), Lots(ALL_MINUS_FLAG | 5)),
+            Some(Lots(0))
+        );
+        assert_eq!(
+            resolve_withdraw_lots(LotBalance(5), Lots(ALL_MINUS_FLAG | 6)),
+            None
+        );
+
+        // A zero buffer withdraws everything
+        assert_eq!(
+            resolve_withdraw_lots(LotBalance(5), Lots(ALL_MINUS_FLAG)),
+            Some(Lots(5))
+        );
+    }
+
+    #[test]
+    fn test_balance_above_u64() {
+        let balance = LotBalance(u64::MAX as u128 + 5);
+
+        assert_eq!(resolve_withdraw_lots(balance, WITHDRAW_ALL), None);
+        assert_eq!(
+            resolve_withdraw_lots(balance, Lots(ALL_MINUS_FLAG | 5)),
+            Some(Lots(u64::MAX))
+        );
+        assert_eq!(
+            resolve_withdraw_lots(balance, Lots(ALL_MINUS_FLAG - 1)),
+            Some(Lots(ALL_MINUS_FLAG - 1))
+        );
+    }
+
+    #[test]
+    fn test_is_exact() {
+        assert!(is_exact(Lots(ALL_MINUS_FLAG - 1)));
+        assert!(!is_exact(Lots(ALL_MINUS_FLAG)));
+        assert!(!is_exact(WITHDRAW_ALL));
+    }
+}